@@ -0,0 +1,99 @@
+use alloc::vec::Vec;
+
+use crate::error::KneedleError;
+use crate::params::KneedleParams;
+use crate::{kneedle_with, Point};
+
+/// The knees of every curve in `curves` with the same `params`, one result per curve in the same
+/// order, as `kneedle_with` gives them.
+///
+/// The curves are searched independently, so an error in one leaves the others untouched. With
+/// the `parallel` feature they are split across the available threads, and every curve is still
+/// searched exactly as the serial version does, so the results are identical.
+///
+/// ```
+/// use kneedle::KneedleParams;
+///
+/// let curves = vec![
+///     vec![[0.0, 0.0], [1.0, 8.0], [2.0, 9.0], [3.0, 9.5], [4.0, 9.75], [5.0, 10.0]],
+///     vec![[0.0, 0.0], [1.0, 1.0]],
+/// ];
+/// let knees = kneedle::kneedle_batch(&curves, &KneedleParams::new().smoothing_window(0));
+/// assert_eq!(Ok(vec![[1.0, 8.0]]), knees[0]);
+/// assert!(knees[1].is_err());
+/// ```
+pub fn kneedle_batch(
+    curves: &[Vec<Point>],
+    params: &KneedleParams,
+) -> Vec<Result<Vec<Point>, KneedleError>> {
+    #[cfg(feature = "parallel")]
+    {
+        let mut results = alloc::vec![Ok(Vec::new()); curves.len()];
+        if curves.is_empty() {
+            return results;
+        }
+
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = curves.len().div_ceil(threads);
+
+        std::thread::scope(|scope| {
+            for (results, curves) in results
+                .chunks_mut(chunk_size)
+                .zip(curves.chunks(chunk_size))
+            {
+                scope.spawn(move || {
+                    for (result, curve) in results.iter_mut().zip(curves) {
+                        *result = kneedle_with(curve, params);
+                    }
+                });
+            }
+        });
+
+        results
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        curves
+            .iter()
+            .map(|curve| kneedle_with(curve, params))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kneedle;
+
+    #[test]
+    fn matches_single_curves() {
+        let concave = vec![
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        //the same shape in other units, and too short a curve to search
+        let scaled: Vec<Point> = concave.iter().map(|&[x, y]| [10.0 * x, 3.0 * y]).collect();
+        let curves = vec![concave, scaled, vec![[0.0, 0.0], [1.0, 1.0]]];
+
+        let params = KneedleParams::new().smoothing_window(1);
+        let knees = kneedle_batch(&curves, &params);
+        assert_eq!(curves.len(), knees.len());
+        for (curve, knees) in curves.iter().zip(&knees) {
+            assert_eq!(&kneedle(curve, 1.0, 1, false), knees);
+        }
+        assert_eq!(Ok(vec![[0.2, 0.75]]), knees[0]);
+        assert_eq!(Ok(vec![[2.0, 2.25]]), knees[1]);
+        assert!(knees[2].is_err());
+
+        assert!(kneedle_batch(&[], &params).is_empty());
+    }
+}
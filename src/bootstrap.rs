@@ -0,0 +1,147 @@
+use alloc::vec::Vec;
+
+use crate::error::KneedleError;
+use crate::float::Float;
+use crate::params::KneedleParams;
+use crate::result::BootstrapResult;
+use crate::{kneedle_ranked, validate};
+
+/// The spread of the knee of `data` over `b` bootstrap resamples, for a confidence interval on
+/// its location in noisy data.
+///
+/// Each resample draws as many rows as `data` holds, with replacement, and keeps the rows drawn
+/// at least once in their original order, so the x-order is preserved and no x-value repeats.
+/// The most prominent knee of each resample, as `kneedle_ranked` orders them, is taken with
+/// `params`; resamples without a knee, or too small to search, are left out. The interval spans
+/// the central 95% of the knee x-values. Returns `Ok(None)` when no resample finds a knee, and
+/// any other error of the search, such as one from invalid `params`, as it is.
+///
+/// The resamples come from a small generator seeded with `seed`, so the same data, parameters
+/// and seed always give the same result, on every platform.
+pub fn kneedle_bootstrap<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+    b: usize,
+    seed: u64,
+) -> Result<Option<BootstrapResult>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let mut rng = SplitMix64(seed);
+    let mut drawn = Vec::with_capacity(data.len());
+    let mut resample: Vec<[T; 2]> = Vec::with_capacity(data.len());
+    let mut knee_x = Vec::with_capacity(b);
+
+    for _ in 0..b {
+        drawn.clear();
+        drawn.extend((0..data.len()).map(|_| rng.below(data.len())));
+        drawn.sort_unstable();
+        drawn.dedup();
+
+        resample.clear();
+        resample.extend(drawn.iter().map(|&i| {
+            let row = data[i].as_ref();
+            [row[0], row[1]]
+        }));
+        //a resample may draw too few distinct rows to search, any other error holds for them all
+        match kneedle_ranked(&resample, params) {
+            Ok(knees) => knee_x.extend(knees.first().map(|knee| knee.x)),
+            Err(KneedleError::TooFewPoints(_)) => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    if knee_x.is_empty() {
+        return Ok(None);
+    }
+    knee_x.sort_by(f64::total_cmp);
+    let quantile = |q: f64| {
+        let position = q * (knee_x.len() - 1) as f64;
+        let (below, fraction) = (position as usize, position - (position as usize) as f64);
+        match knee_x.get(below + 1) {
+            Some(&above) => knee_x[below] + fraction * (above - knee_x[below]),
+            None => knee_x[below],
+        }
+    };
+
+    Ok(Some(BootstrapResult {
+        median_x: quantile(0.5),
+        ci_low: quantile(0.025),
+        ci_high: quantile(0.975),
+        found: knee_x.len(),
+    }))
+}
+
+//splitmix64, small, fast and good enough to draw resamples
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    //uniform below n, by the multiply-shift of Lemire without the rejection step, whose bias is
+    //far below anything a bootstrap can resolve
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{kneedle_with, Point, SmoothingKind};
+
+    #[test]
+    fn brackets_the_clean_knee() {
+        let clean: Vec<Point> = (0..60)
+            .map(|i| {
+                let x = i as f64 / 2.0;
+                [x, 100.0 * (1.0 - (-x / 3.0).exp())]
+            })
+            .collect();
+        //deterministic noise of a few percent of the range
+        let noisy: Vec<Point> = clean
+            .iter()
+            .enumerate()
+            .map(|(i, &[x, y])| [x, y + 3.0 * ((i * 7919 % 23) as f64 / 11.0 - 1.0)])
+            .collect();
+        let params = KneedleParams::new().smoothing_window(2);
+
+        let clean_knee = kneedle_with(&clean, &params).unwrap()[0][0];
+        let result = kneedle_bootstrap(&noisy, &params, 200, 42)
+            .unwrap()
+            .unwrap();
+        assert!(result.found > 150, "{:?}", result);
+        assert!(result.ci_low <= result.median_x && result.median_x <= result.ci_high);
+        assert!(
+            (result.median_x - clean_knee).abs() <= 1.0,
+            "clean knee {} but {:?}",
+            clean_knee,
+            result
+        );
+
+        assert_eq!(
+            Some(result),
+            kneedle_bootstrap(&noisy, &params, 200, 42).unwrap()
+        );
+
+        let line: Vec<Point> = (0..20).map(|i| [i as f64, i as f64]).collect();
+        assert_eq!(None, kneedle_bootstrap(&line, &params, 20, 1).unwrap());
+
+        //parameters that no resample can be searched with are an error, not a missing knee
+        let even = params.smoothing(SmoothingKind::Median { window: 2 });
+        assert!(kneedle_with(&noisy, &even).is_err());
+        assert_eq!(
+            kneedle_with(&noisy, &even).map(|_| None),
+            kneedle_bootstrap(&noisy, &even, 20, 1)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::Read;
+
+use crate::error::KneedleError;
+use crate::Point;
+
+/// Reads two-column numeric CSV into the `[x, y]` rows the detection takes.
+///
+/// Fields are separated by commas and may be wrapped in double quotes. Blank lines are skipped,
+/// and the first line is taken for a header and skipped when one of its fields is not a number.
+/// A field that cannot be parsed, or a line without exactly two fields, gives
+/// `KneedleError::Parse` with the line and field counted from 0.
+///
+/// ```
+/// let csv = "clusters,inertia\n1,100\n2,40\n3,20\n4,15\n5,12\n6,10\n";
+/// let data = kneedle::read_xy_csv(csv.as_bytes()).unwrap();
+/// assert_eq!([2.0, 40.0], data[1]);
+/// ```
+pub fn read_xy_csv<R: Read>(mut reader: R) -> Result<Vec<Point>, KneedleError> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|error| KneedleError::Io(error.kind()))?;
+
+    let mut rows = Vec::new();
+    let mut first = true;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(field).collect();
+        let parsed: Vec<Option<f64>> = fields.iter().map(|field| field.parse().ok()).collect();
+        if first && parsed.iter().any(Option::is_none) {
+            first = false;
+            continue;
+        }
+        first = false;
+
+        match parsed[..] {
+            [Some(x), Some(y)] => rows.push([x, y]),
+            //the first field that is not a number, or the first one missing or left over
+            _ => {
+                let col = parsed
+                    .iter()
+                    .position(Option::is_none)
+                    .unwrap_or(parsed.len());
+                return Err(KneedleError::Parse {
+                    row: line_index,
+                    col: col.min(2),
+                });
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+//a field without the whitespace and quotes around it
+fn field(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    trimmed
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(trimmed)
+        .trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kneedle;
+
+    #[test]
+    fn parses_into_kneedle() {
+        let csv = "x, y\r\n\
+                   0.0, 0.0\r\n\
+                   0.1, 0.55\r\n\
+                   \r\n\
+                   0.2, 0.75\n\
+                   0.35, 0.825\n\
+                   0.45, 0.875\n\
+                   0.55, 0.9\n\
+                   \"0.675\",\"0.925\"\n\
+                   0.775, 0.95\n\
+                   0.875, 0.975\n\
+                   1.0, 1.0\n";
+
+        let data = read_xy_csv(csv.as_bytes()).unwrap();
+        assert_eq!(10, data.len());
+        assert_eq!([0.675, 0.925], data[6]);
+
+        let knee_points = kneedle(&data, 1.0, 1, false).unwrap();
+        assert_eq!(vec![[0.2, 0.75]], knee_points);
+
+        //the same data without a header
+        let headerless = csv.split_once('\n').unwrap().1;
+        assert_eq!(data, read_xy_csv(headerless.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn reports_the_bad_field() {
+        assert_eq!(
+            Err(KneedleError::Parse { row: 2, col: 1 }),
+            read_xy_csv("x,y\n1,2\n2,oops\n".as_bytes())
+        );
+        assert_eq!(
+            Err(KneedleError::Parse { row: 3, col: 2 }),
+            read_xy_csv("1,2\n\n2,3\n3,4,5\n".as_bytes())
+        );
+        assert_eq!(
+            Err(KneedleError::Parse { row: 1, col: 1 }),
+            read_xy_csv("1,2\n2\n".as_bytes())
+        );
+        assert_eq!(
+            Err(KneedleError::Io(std::io::ErrorKind::InvalidData)),
+            read_xy_csv(&[0xff, 0xfe][..])
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use alloc::boxed::Box;
+use core::fmt;
+
+/// Errors returned by the smoothing, normalization and knee detection functions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KneedleError {
+    EmptyData,
+    ZeroDimension,
+    NotTwoDimensional,
+    RaggedRows {
+        row: usize,
+        expected: usize,
+        got: usize,
+    },
+    TooFewPoints(usize),
+    LengthMismatch {
+        x: usize,
+        y: usize,
+    },
+    NonFinite {
+        row: usize,
+        col: usize,
+    },
+    DuplicateX {
+        row: usize,
+    },
+    NonPositiveX {
+        row: usize,
+    },
+    NonPositiveY {
+        row: usize,
+    },
+    NonMonotonic {
+        index: usize,
+    },
+    WeightsMismatch {
+        data: usize,
+        weights: usize,
+    },
+    InvalidWeight {
+        row: usize,
+    },
+    EvenWindow(usize),
+    OrderTooHigh {
+        order: usize,
+        window: usize,
+    },
+    #[cfg(feature = "csv")]
+    Parse {
+        row: usize,
+        col: usize,
+    },
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+    Stage {
+        stage: Stage,
+        error: Box<KneedleError>,
+    },
+}
+
+/// The step of the preparation of the difference curve a `KneedleError::Stage` came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Smoothing,
+    Normalization,
+}
+
+impl KneedleError {
+    //self labelled with the step it came from
+    pub(crate) fn in_stage(self, stage: Stage) -> Self {
+        KneedleError::Stage {
+            stage,
+            error: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for KneedleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KneedleError::EmptyData => write!(f, "Empty data"),
+            KneedleError::ZeroDimension => write!(f, "dimension cannot be 0"),
+            KneedleError::NotTwoDimensional => write!(f, "all data should be 2 dimensional"),
+            KneedleError::RaggedRows { row, expected, got } => write!(
+                f,
+                "all rows must have the same dimension, expected {} but row {} has {}",
+                expected, row, got
+            ),
+            KneedleError::TooFewPoints(got) => {
+                write!(f, "need at least 3 points, got {}", got)
+            }
+            KneedleError::LengthMismatch { x, y } => {
+                write!(f, "x and y must have the same length, got {} and {}", x, y)
+            }
+            KneedleError::NonFinite { row, col } => {
+                write!(f, "non-finite value in row {} column {}", row, col)
+            }
+            KneedleError::DuplicateX { row } => {
+                write!(f, "row {} repeats the x-value of the row before it", row)
+            }
+            KneedleError::NonPositiveX { row } => {
+                write!(
+                    f,
+                    "row {} has an x-value that is not positive on a log scale",
+                    row
+                )
+            }
+            KneedleError::NonPositiveY { row } => {
+                write!(
+                    f,
+                    "row {} has a y-value that is not positive on a log scale",
+                    row
+                )
+            }
+            KneedleError::NonMonotonic { index } => {
+                write!(f, "row {} turns against the direction of the curve", index)
+            }
+            KneedleError::WeightsMismatch { data, weights } => write!(
+                f,
+                "need one weight per row, got {} rows and {} weights",
+                data, weights
+            ),
+            KneedleError::InvalidWeight { row } => {
+                write!(f, "weight of row {} must be positive and finite", row)
+            }
+            KneedleError::EvenWindow(window) => {
+                write!(f, "smoothing window must be odd, got {}", window)
+            }
+            KneedleError::OrderTooHigh { order, window } => write!(
+                f,
+                "polynomial order {} needs a window larger than {}",
+                order, window
+            ),
+            #[cfg(feature = "csv")]
+            KneedleError::Parse { row, col } => {
+                write!(f, "could not parse line {} field {} as a number", row, col)
+            }
+            #[cfg(feature = "std")]
+            KneedleError::Io(kind) => write!(f, "input/output error: {}", kind),
+            KneedleError::Stage { stage, error } => write!(f, "{} failed: {}", stage, error),
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Smoothing => write!(f, "smoothing"),
+            Stage::Normalization => write!(f, "normalization"),
+        }
+    }
+}
+
+//a row converted with Point::try_from that does not hold exactly two values
+impl From<core::array::TryFromSliceError> for KneedleError {
+    fn from(_: core::array::TryFromSliceError) -> Self {
+        KneedleError::NotTwoDimensional
+    }
+}
+
+impl core::error::Error for KneedleError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            KneedleError::Stage { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use core::fmt::Debug;
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Floating point types the algorithms can run on, implemented for `f32` and `f64`.
+///
+/// This is the small subset of `num_traits::Float` the crate needs, so it stays free of
+/// dependencies. Values are `Send` and `Sync` so the `parallel` feature can share them with its
+/// threads.
+pub trait Float:
+    Copy
+    + Send
+    + Sync
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn exp(self) -> Self;
+    fn log10(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn is_nan(self) -> bool;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_float {
+    ($t:ident) => {
+        impl Float for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const MIN: Self = $t::MIN;
+            const MAX: Self = $t::MAX;
+
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[cfg(feature = "std")]
+            fn exp(self) -> Self {
+                $t::exp(self)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn exp(self) -> Self {
+                exp_f64(self as f64) as $t
+            }
+
+            #[cfg(feature = "std")]
+            fn log10(self) -> Self {
+                $t::log10(self)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn log10(self) -> Self {
+                log10_f64(self as f64) as $t
+            }
+
+            #[cfg(feature = "std")]
+            fn sqrt(self) -> Self {
+                $t::sqrt(self)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn sqrt(self) -> Self {
+                sqrt_f64(self as f64) as $t
+            }
+
+            fn abs(self) -> Self {
+                $t::abs(self)
+            }
+
+            fn min(self, other: Self) -> Self {
+                $t::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                $t::max(self, other)
+            }
+
+            fn is_nan(self) -> bool {
+                $t::is_nan(self)
+            }
+
+            fn is_finite(self) -> bool {
+                $t::is_finite(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+//exp without std, from exp(x) = 2^k * exp(r) with x = k * ln(2) + r and |r| <= ln(2) / 2, where a
+//Taylor series to r^13 is exact to double precision
+#[cfg(any(not(feature = "std"), test))]
+fn exp_f64(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+    if x > 709.8 {
+        return f64::INFINITY;
+    }
+    if x < -745.2 {
+        return 0.0;
+    }
+
+    //ln(2) split so that k * LN_2_HI is exact and the reduction loses no precision
+    const LN_2_HI: f64 = f64::from_bits(0x3fe6_2e42_fee0_0000);
+    const LN_2_LO: f64 = f64::from_bits(0x3dea_39ef_3579_3c76);
+
+    let k = x / core::f64::consts::LN_2;
+    let k = if k < 0.0 { k - 0.5 } else { k + 0.5 } as i32;
+    let r = (x - k as f64 * LN_2_HI) - k as f64 * LN_2_LO;
+
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..=13 {
+        term *= r / n as f64;
+        sum += term;
+    }
+
+    //split the power of two so that subnormal results and 2^1024 stay representable
+    let half = k / 2;
+    sum * pow2(half) * pow2(k - half)
+}
+
+#[cfg(any(not(feature = "std"), test))]
+fn pow2(k: i32) -> f64 {
+    f64::from_bits(((k + 1023) as u64) << 52)
+}
+
+//log10 without std, from ln(x) = e * ln(2) + ln(m) with x = 2^e * m and sqrt(1/2) <= m < sqrt(2),
+//where ln(m) = 2 * atanh((m - 1) / (m + 1)) is a series in odd powers of an argument below 0.18
+#[cfg(any(not(feature = "std"), test))]
+fn log10_f64(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if x == f64::INFINITY {
+        return x;
+    }
+
+    //scale subnormals up so their exponent can be read off the bits
+    let (x, scaled) = if x < f64::MIN_POSITIVE {
+        (x * pow2(54), 54)
+    } else {
+        (x, 0)
+    };
+    let bits = x.to_bits();
+    let mut e = ((bits >> 52) as i32) - 1023 - scaled;
+    let mut m = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | 0x3ff0_0000_0000_0000);
+    if m > core::f64::consts::SQRT_2 {
+        m /= 2.0;
+        e += 1;
+    }
+
+    let s = (m - 1.0) / (m + 1.0);
+    let s2 = s * s;
+    let mut term = s;
+    let mut sum = s;
+    for n in 1..=13 {
+        term *= s2;
+        sum += term / (2 * n + 1) as f64;
+    }
+
+    (e as f64 * core::f64::consts::LN_2 + 2.0 * sum) / core::f64::consts::LN_10
+}
+
+//sqrt without std, by Newton's method from a first guess that halves the exponent, which is
+//within a factor of two of the root so that six steps reach double precision
+#[cfg(any(not(feature = "std"), test))]
+fn sqrt_f64(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 || x == f64::INFINITY {
+        return x;
+    }
+
+    //scale subnormals up so the guess stays within range
+    let (x, scale) = if x < f64::MIN_POSITIVE {
+        (x * pow2(54), pow2(-27))
+    } else {
+        (x, 1.0)
+    };
+    let mut root = f64::from_bits((x.to_bits() >> 1) + (0x3ff0_0000_0000_0000 >> 1));
+    for _ in 0..6 {
+        root = (root + x / root) / 2.0;
+    }
+    root * scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exp_f64_matches_std() {
+        //relative to double precision, and to a few ulps once the result is subnormal
+        for i in -7450..=7090 {
+            let x = i as f64 / 10.0 + 0.037;
+            let (expected, got) = (x.exp(), exp_f64(x));
+            assert!(
+                (expected - got).abs() <= expected * 1e-14 + 1e-322,
+                "exp({}) = {} but got {}",
+                x,
+                expected,
+                got
+            );
+        }
+        assert_eq!(1.0, exp_f64(0.0));
+        assert_eq!(0.0, exp_f64(-800.0));
+        assert_eq!(f64::INFINITY, exp_f64(800.0));
+        assert!(exp_f64(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn log10_f64_matches_std() {
+        for i in -3230..=3080 {
+            let x = 10f64.powf(i as f64 / 10.0 + 0.037);
+            let (expected, got) = (x.log10(), log10_f64(x));
+            assert!(
+                (expected - got).abs() <= expected.abs() * 1e-14 + 1e-15,
+                "log10({}) = {} but got {}",
+                x,
+                expected,
+                got
+            );
+        }
+        assert_eq!(0.0, log10_f64(1.0));
+        assert_eq!(f64::NEG_INFINITY, log10_f64(0.0));
+        assert_eq!(f64::INFINITY, log10_f64(f64::INFINITY));
+        assert!(log10_f64(-1.0).is_nan());
+    }
+
+    #[test]
+    fn sqrt_f64_matches_std() {
+        for i in -3230..=3080 {
+            let x = 10f64.powf(i as f64 / 10.0 + 0.037);
+            let (expected, got) = (x.sqrt(), sqrt_f64(x));
+            assert!(
+                (expected - got).abs() <= expected * 1e-15,
+                "sqrt({}) = {} but got {}",
+                x,
+                expected,
+                got
+            );
+        }
+        assert_eq!(3.0, sqrt_f64(9.0));
+        assert_eq!(0.0, sqrt_f64(0.0));
+        assert_eq!(f64::INFINITY, sqrt_f64(f64::INFINITY));
+        assert!(sqrt_f64(-1.0).is_nan());
+    }
+}
@@ -1,152 +1,3373 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod batch;
+mod bootstrap;
+#[cfg(feature = "csv")]
+mod csv;
+mod error;
+mod float;
 mod maths;
-use approx_eq::assert_approx_eq;
+pub mod methods;
+mod params;
+#[cfg(feature = "debug-svg")]
+mod plot;
+mod preprocess;
+mod result;
+mod streaming;
+mod workspace;
+
+pub use crate::batch::kneedle_batch;
+pub use crate::bootstrap::kneedle_bootstrap;
+#[cfg(feature = "csv")]
+pub use crate::csv::read_xy_csv;
+pub use crate::error::{KneedleError, Stage};
+pub use crate::float::Float;
+use crate::maths::gaussian_smooth;
+#[cfg(feature = "parallel")]
+pub use crate::maths::gaussian_smooth2d_parallel;
+pub use crate::maths::{
+    ema_smooth_y, gaussian_kernel, gaussian_smooth2d, gaussian_smooth2d_with_bandwidth,
+    gaussian_smooth2d_with_boundary, gaussian_smooth_by_x, gaussian_smooth_y, median_smooth_y,
+    minmax_normalize, savitzky_golay_smooth_y, zscore_normalize,
+};
+pub use crate::params::{
+    Boundary, Curve, DuplicateX, KneedleParams, NonFinite, Normalization, Selection, SmoothingKind,
+    ThresholdMode, XOrder, XScale, YScale,
+};
+#[cfg(feature = "debug-svg")]
+pub use crate::plot::{render_debug, render_debug_svg};
+use crate::preprocess::{
+    first_duplicate_x, first_non_finite, first_non_monotonic, is_descending, keeps_rows, rows_for,
+    Rows,
+};
+pub use crate::result::{BootstrapResult, CandidateInfo, KneeResult, KneedleTrace, SweepResult};
+pub use crate::streaming::{KneeIteratorExt, Knees, StreamingKneedle};
+pub use crate::workspace::KneedleWorkspace;
+
+/// A point of a curve as `[x, y]`, the form every `f64` point takes in and out of the crate.
+///
+/// The generic entry points take rows of any `Float` as `[T; 2]` or any other `AsRef<[T]>`, and
+/// return the knee rows as `[T; 2]`, which is a `Point` for `f64` data.
+///
+/// Being an array, a `Point` converts from an `(x, y)` tuple with `Point::from` and from a slice
+/// with `Point::try_from`, which fails unless the slice holds exactly two values. That failure
+/// converts into `KneedleError::NotTwoDimensional`, so `?` works on it in a function returning a
+/// `KneedleError`.
+///
+/// ```
+/// use kneedle::{KneedleError, Point};
+///
+/// let tuples = [(0.0, 0.0), (1.0, 8.0)];
+/// let points: Vec<Point> = tuples.iter().copied().map(Point::from).collect();
+/// assert_eq!(vec![[0.0, 0.0], [1.0, 8.0]], points);
+///
+/// let row: &[f64] = &[1.0, 8.0, 3.0];
+/// let point: Result<Point, KneedleError> = Point::try_from(row).map_err(KneedleError::from);
+/// assert_eq!(Err(KneedleError::NotTwoDimensional), point);
+/// ```
+pub type Point = [f64; 2];
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+//a knee as found by the detection, before it is mapped onto the caller's representation
+#[derive(Clone, Debug)]
+struct Knee<T> {
+    index: usize,
+    diff_value: T,
+    threshold: T,
+    prominence: T,
+}
+
+//the local extrema of the difference curve, with include_endpoints the first and last points too
+//when they compare as one with the single neighbour they have. a run of equal values counts as a
+//single point, so a flat extremum gives one candidate, at its first point, or at the last point
+//for a run that ends the curve. an extremum must also stand out from its neighbours by at least
+//tolerance, so wobble below it in a flat stretch gives no candidates
+fn find_candidate_indices<T: Float, R: AsRef<[T]>>(
+    data: &[R],
+    find_minima: bool,
+    include_endpoints: bool,
+    tolerance: T,
+    candidates: &mut Vec<usize>,
+) {
+    let rows = data.len();
+    let value = |i: usize| data[i].as_ref()[1];
+    let beyond = |cur: T, neighbour: T| {
+        if find_minima {
+            neighbour > cur && neighbour - cur >= tolerance
+        } else {
+            neighbour < cur && cur - neighbour >= tolerance
+        }
+    };
+
+    candidates.clear();
+    let mut start = 0;
+    while start < rows {
+        let cur = value(start);
+        let mut end = start;
+        while end + 1 < rows && value(end + 1) == cur {
+            end += 1;
+        }
+
+        let prev = start.checked_sub(1).map(value);
+        let next = (end + 1 < rows).then(|| value(end + 1));
+        match (prev, next) {
+            (Some(prev), Some(next)) if beyond(cur, prev) && beyond(cur, next) => {
+                candidates.push(start)
+            }
+            (None, Some(next)) if include_endpoints && beyond(cur, next) => candidates.push(start),
+            (Some(prev), None) if include_endpoints && beyond(cur, prev) => candidates.push(end),
+            _ => {}
+        }
+        start = end + 1;
+    }
+}
+
+/// Which local extrema `find_extrema` returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extremum {
+    Minima,
+    Maxima,
+    /// Both, in order of index.
+    Both,
+}
+
+/// Indices of the local extrema in the y-values of `curve`, the points above or below both
+/// their neighbours, such as the peaks and troughs of a smoothed curve.
+///
+/// These are the candidates of step 4 of the paper without the threshold that follows. A run of
+/// equal values counts as a single point, at its first index, and the first and last points are
+/// never extrema as they have only one neighbour.
+///
+/// ```
+/// use kneedle::Extremum;
+///
+/// let curve = [[0.0, 0.0], [1.0, 2.0], [2.0, 1.0], [3.0, 3.0], [4.0, 2.0]];
+/// assert_eq!(vec![1, 3], kneedle::find_extrema(&curve, Extremum::Maxima));
+/// assert_eq!(vec![1, 2, 3], kneedle::find_extrema(&curve, Extremum::Both));
+/// ```
+pub fn find_extrema(curve: &[Point], kind: Extremum) -> Vec<usize> {
+    let mut extrema = Vec::new();
+    match kind {
+        Extremum::Minima | Extremum::Maxima => {
+            let minima = kind == Extremum::Minima;
+            find_candidate_indices(curve, minima, false, 0.0, &mut extrema);
+        }
+        Extremum::Both => {
+            let mut maxima = Vec::new();
+            find_candidate_indices(curve, true, false, 0.0, &mut extrema);
+            find_candidate_indices(curve, false, false, 0.0, &mut maxima);
+            extrema.append(&mut maxima);
+            extrema.sort_unstable();
+        }
+    }
+    extrema
+}
+
+/// Index of the largest value of a difference curve, the single global knee that the original
+/// Kneedle formulation reports, or `None` when `curve` has no value that is not NaN.
+///
+/// Knees are maxima of the difference curve, pass the negated values to find an elbow. Ties go
+/// to the first index.
+///
+/// ```
+/// assert_eq!(Some(2), kneedle::max_difference_index(&[0.0, 0.3, 0.5, f64::NAN, 0.5, 0.1]));
+/// ```
+pub fn max_difference_index<T: Float>(curve: &[T]) -> Option<usize> {
+    let mut best: Option<(usize, T)> = None;
+
+    for (i, &value) in curve.iter().enumerate() {
+        if value.is_nan() {
+            continue;
+        }
+        match best {
+            Some((_, best_value)) if best_value >= value => {}
+            _ => best = Some((i, value)),
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+//step 1 of the paper, with the smoothing chosen in params
+fn smooth<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    match params.smoothing {
+        SmoothingKind::Gaussian => {
+            let w = params.smoothing_window;
+            let mut smoothed =
+                gaussian_smooth(data, w, params.gaussian_bandwidth, params.boundary)?;
+            if params.smooth_y_only {
+                for (smoothed_row, row) in smoothed.iter_mut().zip(data) {
+                    smoothed_row[0] = row.as_ref()[0];
+                }
+            }
+            Ok(smoothed)
+        }
+        SmoothingKind::GaussianDistance { bandwidth } => gaussian_smooth_by_x(data, bandwidth),
+        SmoothingKind::Median { window } => median_smooth_y(data, window),
+        SmoothingKind::SavitzkyGolay { window, order } => {
+            savitzky_golay_smooth_y(data, window, order)
+        }
+        SmoothingKind::Ema { alpha } => ema_smooth_y(data, alpha),
+    }
+}
+
+fn prepare<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<[T; 2]>, KneedleError> {
+    Ok(KneedleWorkspace::new().prepare(data, params)?.to_vec())
+}
+
+/// The curve after the smoothing and normalization of `params` (steps 1 and 2 of the paper),
+/// without the difference step that is specific to Kneedle, for analysis of its own.
+///
+/// `data` must hold at least 3 two-dimensional rows and is used as it is, neither reordered nor
+/// cleaned up. With `Normalization::MinMax` both dimensions lie in the unit range, see
+/// `difference_curve` for the curve with y - x taken.
+pub fn smooth_and_normalize<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+    Ok(KneedleWorkspace::new()
+        .smooth_and_normalize(data, params)?
+        .to_vec())
+}
+
+/// Returns the normalized difference curve the knee search runs on, for plotting, debugging or
+/// building other detectors on the same preprocessing (steps 1 to 3 of the paper).
+///
+/// `data` must hold at least 3 two-dimensional rows. Each point is `[x, y - x]` where `x` and `y`
+/// are the smoothed values scaled into the unit range, so the x-values stay in normalized space
+/// rather than the units of `data`.
+pub fn difference_curve<T, I>(
+    data: &[I],
+    smoothing_window: usize,
+) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    prepare(
+        data,
+        &KneedleParams::new().smoothing_window(smoothing_window),
+    )
+}
+
+/// Area between the normalized curve and the diagonal from its first to its last point, by the
+/// trapezoidal rule over `difference_curve`, a single number for how far a whole curve bends.
+///
+/// The absolute difference is integrated, so bends either way add up. A straight line gives 0,
+/// a curve that rises at once and then stays flat, a sharp knee, approaches the 0.5 of a right
+/// angle.
+pub fn difference_curve_area<T, I>(data: &[I], smoothing_window: usize) -> Result<f64, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let curve = difference_curve(data, smoothing_window)?;
+    Ok(curve
+        .windows(2)
+        .map(|pair| {
+            let [[x0, d0], [x1, d1]] = [pair[0], pair[1]];
+            (x1 - x0).to_f64().abs() * (d0.abs() + d1.abs()).to_f64() / 2.0
+        })
+        .sum())
+}
+
+/// Runs only the knee search (steps 4 to 6 of the paper) on a curve that is already prepared,
+/// for data smoothed and normalized in some other way, or to experiment with the preprocessing.
+///
+/// A prepared curve is what `difference_curve` returns: rows `[x, y - x]` where `x` and `y` were
+/// scaled into the unit range, x ascending, and a decreasing curve already flipped along x so
+/// that it increases. Nothing is smoothed, normalized or reordered, and the knee points are
+/// returned as rows of `prepared_curve`. Knees are the maxima of the difference curve, elbows
+/// when `find_elbow` is set its minima.
+pub fn kneedle_prepared<T: Float>(
+    prepared_curve: &[[T; 2]],
+    s: f64,
+    find_elbow: bool,
+) -> Result<Vec<[T; 2]>, KneedleError> {
+    validate(prepared_curve)?;
+    if let Some((row, col)) = first_non_finite(prepared_curve) {
+        return Err(KneedleError::NonFinite { row, col });
+    }
+
+    let mut step = mean_x_step(prepared_curve) * T::from_f64(s);
+    if !find_elbow {
+        step = -step;
+    }
+
+    let mut knees = Vec::new();
+    threshold_knees(
+        prepared_curve,
+        step,
+        find_elbow,
+        false,
+        T::ZERO,
+        &mut Vec::new(),
+        &mut knees,
+    );
+    Ok(knees
+        .into_iter()
+        .map(|knee| prepared_curve[knee.index])
+        .collect())
+}
+
+//mean gap between consecutive x-values, the sum(x_{i+1} - x_i) / (n - 1) that step 5 of the paper
+//scales by S to get the threshold drop (on normalized data this is just 1 / (n - 1) for sorted x),
+//zero for fewer than two rows, which have no gap to average
+fn mean_x_step<T: Float, R: AsRef<[T]>>(data: &[R]) -> T {
+    let mut total = T::ZERO;
+    if data.len() < 2 {
+        return total;
+    }
+
+    for i in 0..data.len() - 1 {
+        total += data[i + 1].as_ref()[0] - data[i].as_ref()[0];
+    }
+    total / T::from_f64((data.len() - 1) as f64)
+}
+
+//distance between the smallest and the largest x-value, zero for no rows
+fn x_span<T: Float, R: AsRef<[T]>>(data: &[R]) -> T {
+    let mut span = None;
+    for row in data {
+        let x = row.as_ref()[0];
+        span = Some(match span {
+            None => (x, x),
+            Some((min, max)) => (x.min(min), x.max(max)),
+        });
+    }
+    span.map_or(T::ZERO, |(min, max)| max - min)
+}
+
+//below this mean second derivative (in normalized units) a curve is treated as a straight line
+const LINEARITY_TOLERANCE: f64 = 1e-3;
+
+//classify normalized (but not yet differenced) data by its direction and the sign of its average
+//second derivative, None when the curve is too close to a straight line to tell
+fn classify_curve<T: Float>(normalized_data: &[Vec<T>]) -> Option<Curve> {
+    let first = normalized_data.first()?;
+    let last = normalized_data.last()?;
+    if first[1] == last[1] {
+        return None;
+    }
+    let increasing = last[1] > first[1];
+
+    let mut sum_second_diff = 0.0;
+    let mut count = 0;
+    for window in normalized_data.windows(3) {
+        let [a, b, c] =
+            [&window[0], &window[1], &window[2]].map(|row| [row[0].to_f64(), row[1].to_f64()]);
+        if a[0] == b[0] || b[0] == c[0] || a[0] == c[0] {
+            continue;
+        }
+        let left_slope = (b[1] - a[1]) / (b[0] - a[0]);
+        let right_slope = (c[1] - b[1]) / (c[0] - b[0]);
+        sum_second_diff += (right_slope - left_slope) / ((c[0] - a[0]) / 2.0);
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+
+    let mean_second_diff = sum_second_diff / count as f64;
+    if mean_second_diff.abs() < LINEARITY_TOLERANCE {
+        return None;
+    }
+
+    let convex = mean_second_diff > 0.0;
+    Some(match (convex, increasing) {
+        (true, true) => Curve::ConvexIncreasing,
+        (true, false) => Curve::ConvexDecreasing,
+        (false, true) => Curve::ConcaveIncreasing,
+        (false, false) => Curve::ConcaveDecreasing,
+    })
+}
+
+/// Mirrors `data` along x, turning a decreasing curve into an increasing one: every x becomes
+/// `xmax - x` and the rows are reversed.
+///
+/// The reversal assumes `data` is sorted by ascending x, which then gives flipped rows in
+/// ascending x again. Rows in any other order are reversed all the same, so flipped data is only
+/// sorted when the input was. `flip_x_mapped` also returns the mapping back onto `data`.
+pub fn flip_x<T, I>(data: &[I]) -> Vec<Vec<T>>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    flip_x_mapped(data).0
+}
+
+/// Same as `flip_x`, with the `XFlip` that maps positions and x-values of the flipped data back
+/// onto `data`, for reporting knees found on flipped data in the original coordinates.
+///
+/// ```
+/// let data = [[0.0, 10.0], [1.0, 4.0], [2.0, 2.0], [3.0, 1.0]];
+/// let (flipped, flip) = kneedle::flip_x_mapped(&data);
+/// assert_eq!(vec![0.0, 1.0], flipped[0]);
+/// assert_eq!(3, flip.index(0));
+/// assert_eq!(3.0, flip.x(0.0));
+/// ```
+pub fn flip_x_mapped<T, I>(data: &[I]) -> (Vec<Vec<T>>, XFlip<T>)
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let mut xmax = T::MIN;
+
+    for row in data {
+        xmax = xmax.max(row.as_ref()[0]);
+    }
+
+    let mut out = Vec::new();
+
+    for row in data.iter().rev() {
+        out.push(vec![xmax - row.as_ref()[0], row.as_ref()[1]]);
+    }
+
+    (
+        out,
+        XFlip {
+            xmax,
+            len: data.len(),
+        },
+    )
+}
+
+/// The mirroring applied by `flip_x_mapped`, for going from flipped data back to the original.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct XFlip<T> {
+    xmax: T,
+    len: usize,
+}
+
+impl<T: Float> XFlip<T> {
+    /// Position in the original data of the row at `flipped_index` of the flipped data.
+    pub fn index(&self, flipped_index: usize) -> usize {
+        self.len - 1 - flipped_index
+    }
+
+    /// Original x-value of the flipped x-value `flipped_x`.
+    pub fn x(&self, flipped_x: T) -> T {
+        self.xmax - flipped_x
+    }
+
+    /// The original data back from `flipped`, exact up to the rounding of `xmax - x`.
+    pub fn unflip<I: AsRef<[T]>>(&self, flipped: &[I]) -> Vec<Vec<T>> {
+        flipped
+            .iter()
+            .rev()
+            .map(|row| vec![self.x(row.as_ref()[0]), row.as_ref()[1]])
+            .collect()
+    }
+}
+
+/// Finds the knee points of `data`, or its elbow points when `find_elbow` is set, with
+/// sensitivity `s` after Gaussian smoothing over `smoothing_window` samples either side.
+///
+/// An empty `Vec` means the input was valid but the curve has no knee, as for a straight line,
+/// or a curve whose difference curve has no local maximum (no local minimum for elbows) between
+/// its ends, which leaves nothing to confirm against the threshold.
+/// Input or parameters that cannot be searched always give an `Err`, so the two cannot be
+/// mistaken for one another. `has_knee` asks the question directly.
+///
+/// Two extrema of the difference curve at exactly the same height, without a drop past the
+/// threshold between them, count as one knee, reported at the first of them in the search: the
+/// smaller x of an increasing curve, the larger x of a decreasing one, which is searched flipped.
+pub fn kneedle<T, I>(
+    data: &[I],
+    s: f64,
+    smoothing_window: usize,
+    find_elbow: bool,
+) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(smoothing_window)
+        .elbow(find_elbow);
+    kneedle_with(data, &params)
+}
+
+/// Same as `kneedle`, with every error collapsed into `None`, for quick scripts that only care
+/// whether knees were found.
+///
+/// `kneedle` remains the function to call when the reason matters: `None` does not tell malformed
+/// input from invalid parameters. `Some` of an empty `Vec` still means the curve has no knee, and
+/// `unwrap_or_default` treats both cases alike.
+///
+/// ```
+/// let data = [[0.0, 0.0], [1.0, 8.0], [2.0, 9.0], [3.0, 9.5], [4.0, 9.75], [5.0, 10.0]];
+/// assert_eq!(Some(vec![[1.0, 8.0]]), kneedle::try_kneedle(&data, 1.0, 0, false));
+/// assert!(kneedle::try_kneedle(&data[..2], 1.0, 0, false).unwrap_or_default().is_empty());
+/// ```
+pub fn try_kneedle<T, I>(
+    data: &[I],
+    s: f64,
+    smoothing_window: usize,
+    find_elbow: bool,
+) -> Option<Vec<[T; 2]>>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    kneedle(data, s, smoothing_window, find_elbow).ok()
+}
+
+/// Same as `kneedle`, with the parameters given by name.
+///
+/// Each call allocates its own buffers, `KneedleWorkspace` reuses them across calls.
+pub fn kneedle_with<T, I>(data: &[I], params: &KneedleParams) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    Ok(KneedleWorkspace::new().kneedle(data, params)?.to_vec())
+}
+
+/// Same as `kneedle_with`, returning references to the knee rows of `data` instead of copies of
+/// their first two values, which suits rows that carry more than the point itself.
+///
+/// The references borrow from `data`, not from `params` or the detection, so they stay valid
+/// for as long as `data` does.
+pub fn kneedle_borrowed<'a, T, I>(
+    data: &'a [I],
+    params: &KneedleParams,
+) -> Result<Vec<&'a I>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let knees = find_knees(data, params)?;
+    Ok(knees.into_iter().map(|knee| &data[knee.index]).collect())
+}
+
+/// Same as `kneedle_with`, with each knee returned together with the rows of `data` just before
+/// and just after it, `(before, knee, after)`, for fitting a local model around the bend.
+///
+/// The neighbours are the adjacent rows of `data` as given; a knee on the first or last row has
+/// itself in place of the missing neighbour.
+///
+/// ```
+/// use kneedle::KneedleParams;
+///
+/// let data = [[0.0, 0.0], [1.0, 8.0], [2.0, 9.0], [3.0, 9.5], [4.0, 9.75], [5.0, 10.0]];
+/// let knees = kneedle::kneedle_with_neighbors(&data, &KneedleParams::new().smoothing_window(0));
+/// assert_eq!(Ok(vec![([0.0, 0.0], [1.0, 8.0], [2.0, 9.0])]), knees);
+/// ```
+pub fn kneedle_with_neighbors<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<(Point, Point, Point)>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let point = |i: usize| {
+        let row = data[i].as_ref();
+        [row[0].to_f64(), row[1].to_f64()]
+    };
+    let knees = find_knees(data, params)?;
+    Ok(knees
+        .into_iter()
+        .map(|knee| {
+            let before = knee.index.saturating_sub(1);
+            let after = (knee.index + 1).min(data.len() - 1);
+            (point(before), point(knee.index), point(after))
+        })
+        .collect())
+}
+
+/// Same as `kneedle_with`, with every intermediate stage of the detection returned alongside the
+/// knees, see `KneedleTrace`.
+///
+/// The stages are copies of the buffers the search itself ran on, taken in the same single pass
+/// that finds the knees, so they always agree with them.
+pub fn kneedle_trace<T, I>(data: &[I], params: &KneedleParams) -> Result<KneedleTrace, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    //the stages are recorded by the search itself as it runs
+    let mut workspace = KneedleWorkspace::new();
+    workspace.trace = Some(KneedleTrace::default());
+    workspace.find_knees(data, None, None, params)?;
+
+    let mut trace = workspace.trace.take().unwrap_or_default();
+    trace.knees = workspace
+        .knees
+        .iter()
+        .map(|knee| {
+            let row = data[knee.index].as_ref();
+            [row[0].to_f64(), row[1].to_f64()]
+        })
+        .collect();
+    Ok(trace)
+}
+
+/// Every candidate the search of `kneedle_with` tested, in the order of `data`, with the
+/// threshold it was held to and whether it passed, for seeing how close the rejected ones came
+/// when tuning the sensitivity.
+///
+/// A candidate can only pass when the difference curve crosses its threshold before the next
+/// candidate, so one that is reported as failing with a threshold it later crossed was cut short.
+/// `min_separation` and `selection` are not applied, `passed` is the outcome of step 6 alone.
+pub fn candidate_report<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<CandidateInfo>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let rows = rows_for(data, params)?;
+    let decreasing = params.curve.is_decreasing();
+    let searched: Vec<Vec<T>> = if decreasing {
+        flip_x(&rows.points)
+    } else {
+        rows.points.iter().map(|point| point.to_vec()).collect()
+    };
+
+    let mut workspace = KneedleWorkspace::new();
+    let curve = workspace.prepare(&searched, params)?;
+    let step = threshold_step(curve, params);
+    let find_elbow = params.curve.is_convex();
+
+    let mut candidates = Vec::new();
+    let mut knees = Vec::new();
+    threshold_knees(
+        curve,
+        step,
+        find_elbow,
+        params.include_endpoints,
+        T::from_f64(params.extremum_tolerance),
+        &mut candidates,
+        &mut knees,
+    );
+
+    let n = curve.len();
+    let mut report: Vec<CandidateInfo> = candidates
+        .iter()
+        .map(|&i| {
+            let index = if decreasing { n - 1 - i } else { i };
+            let diff_height = curve[i][1];
+            CandidateInfo {
+                index: rows.origin[index],
+                diff_height: diff_height.to_f64(),
+                threshold: (diff_height + step).to_f64(),
+                passed: knees.iter().any(|knee| knee.index == i),
+            }
+        })
+        .collect();
+    report.sort_by_key(|candidate| candidate.index);
+    Ok(report)
+}
+
+pub(crate) fn to_f64_points<T: Float, R: AsRef<[T]>>(rows: &[R]) -> Vec<Point> {
+    rows.iter()
+        .map(|row| [row.as_ref()[0].to_f64(), row.as_ref()[1].to_f64()])
+        .collect()
+}
+
+/// Same as `kneedle_with`, with each row of `data` counting for `weights` of the same row in the
+/// Gaussian smoothing, so that less reliable samples are pulled toward their neighbours.
+///
+/// The Gaussian weight of every neighbour is multiplied by its entry in `weights`, all ones give
+/// the same result as `kneedle_with`. Weights must be positive and finite, one per row. Only
+/// `SmoothingKind::Gaussian` uses them, the other smoothers ignore them.
+pub fn kneedle_weighted<T, I>(
+    data: &[I],
+    weights: &[f64],
+    params: &KneedleParams,
+) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    if weights.len() != data.len() {
+        return Err(KneedleError::WeightsMismatch {
+            data: data.len(),
+            weights: weights.len(),
+        });
+    }
+    if let Some(row) = weights
+        .iter()
+        .position(|&weight| !weight.is_finite() || weight <= 0.0)
+    {
+        return Err(KneedleError::InvalidWeight { row });
+    }
+
+    let mut workspace = KneedleWorkspace::new();
+    workspace.find_knees(data, Some(weights), None, params)?;
+    Ok(workspace
+        .knees
+        .iter()
+        .map(|knee| {
+            let row = data[knee.index].as_ref();
+            [row[0], row[1]]
+        })
+        .collect())
+}
+
+/// Same as `kneedle_with`, with the candidate knees chosen by `detector` instead of the local
+/// extrema of the difference curve, for bringing knowledge of where a bend can occur.
+///
+/// `detector` is given the normalized difference curve as `difference_curve` returns it, oriented
+/// as it is searched, so a decreasing curve arrives flipped along x, and returns indices into it.
+/// Each candidate is then kept or rejected by the threshold of step 5 of the paper as the
+/// built-in ones are. Indices past the end of the curve are ignored, and the order and repeats of
+/// the others do not matter. `params.include_endpoints` is left to the detector.
+pub fn kneedle_with_candidates<T, I, F>(
+    data: &[I],
+    params: &KneedleParams,
+    mut detector: F,
+) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+    F: FnMut(&[Point]) -> Vec<usize>,
+{
+    let mut workspace = KneedleWorkspace::new();
+    workspace.find_knees(data, None, Some(&mut detector), params)?;
+    Ok(workspace
+        .knees
+        .iter()
+        .map(|knee| {
+            let row = data[knee.index].as_ref();
+            [row[0], row[1]]
+        })
+        .collect())
+}
+
+/// Whether the detection with `params` finds any knee in `data`.
+///
+/// `Ok(false)` is a valid curve without a knee, unlike `Err` which is input or parameters that
+/// cannot be searched.
+pub fn has_knee<T, I>(data: &[I], params: &KneedleParams) -> Result<bool, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    Ok(!find_knees(data, params)?.is_empty())
+}
+
+/// Same as `kneedle`, but returns the positions in `data` of the detected knee/elbow points
+/// instead of copies of the points themselves.
+pub fn kneedle_indices<T, I>(
+    data: &[I],
+    s: f64,
+    smoothing_window: usize,
+    find_elbow: bool,
+) -> Result<Vec<usize>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(smoothing_window)
+        .elbow(find_elbow);
+    let knees = find_knees(data, &params)?;
+    Ok(knees.into_iter().map(|knee| knee.index).collect())
+}
+
+/// Same as `kneedle_with`, but reports the x of each knee as a fraction of the x-range of
+/// `data`, 0 at the smallest x and 1 at the largest, for comparing knees across curves on
+/// different scales.
+///
+/// Rows whose x is not finite do not count toward the range. A range of zero width, where every
+/// x is the same, puts every knee at 0.
+pub fn kneedle_fraction<T, I>(data: &[I], params: &KneedleParams) -> Result<Vec<f64>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let knees = find_knees(data, params)?;
+    Ok(knees
+        .iter()
+        .map(|knee| x_fraction(data, data[knee.index].as_ref()[0].to_f64()))
+        .collect())
+}
+
+/// Same as `kneedle_with`, but reports each knee as a `KneeResult` with its position in `data`
+/// and the height of the difference curve behind the detection.
+pub fn kneedle_detailed<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<KneeResult>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let knees = find_knees(data, params)?;
+    Ok(knees
+        .iter()
+        .map(|knee| knee_result(data, knee.index, knee))
+        .collect())
+}
+
+//a knee reported from row index of data
+fn knee_result<T: Float, I: AsRef<[T]>>(data: &[I], index: usize, knee: &Knee<T>) -> KneeResult {
+    KneeResult {
+        x: data[index].as_ref()[0].to_f64(),
+        y: data[index].as_ref()[1].to_f64(),
+        index,
+        diff_value: knee.diff_value.to_f64(),
+        threshold: knee.threshold.to_f64(),
+        prominence: knee.prominence.to_f64(),
+    }
+}
+
+/// The elbows and the knees of an increasing curve, in that order, as `kneedle_detailed` finds
+/// them with `KneedleParams::elbow` set and unset, for bracketing a transition such as the
+/// middle of an S-shaped curve.
+///
+/// Both searches run on the same difference curve, which is smoothed and normalized only once:
+/// elbows are its minima and knees its maxima.
+pub fn detect_elbow_and_knee<T, I>(
+    data: &[I],
+    s: f64,
+    smoothing_window: usize,
+) -> Result<(Vec<KneeResult>, Vec<KneeResult>), KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(smoothing_window);
+    let rows = rows_for(data, &params)?;
+
+    let mut workspace = KneedleWorkspace::new();
+    let curve = workspace.prepare(&rows.points, &params)?;
+    let step = mean_x_step(curve) * T::from_f64(s);
+
+    let mut candidates = Vec::new();
+    let mut knees = Vec::new();
+    let mut search = |find_elbow: bool| {
+        let step = if find_elbow { step } else { -step };
+        threshold_knees(
+            curve,
+            step,
+            find_elbow,
+            false,
+            T::ZERO,
+            &mut candidates,
+            &mut knees,
+        );
+        knees
+            .iter()
+            .map(|knee| knee_result(data, rows.origin[knee.index], knee))
+            .collect()
+    };
+    let elbows = search(true);
+    Ok((elbows, search(false)))
+}
+
+/// Same as `kneedle_detailed`, but sorted by the strength of each knee so the first element is
+/// the dominant one.
+///
+/// Knees are ordered by the magnitude of the difference curve at the knee, largest first, with
+/// ties going to the smaller x.
+pub fn kneedle_ranked<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<KneeResult>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let mut knees = kneedle_detailed(data, params)?;
+    knees.sort_by(|a, b| {
+        b.diff_value
+            .abs()
+            .total_cmp(&a.diff_value.abs())
+            .then(a.x.total_cmp(&b.x))
+    });
+    Ok(knees)
+}
+
+/// The most prominent knee of `data` as an `[x, y]` point, `None` when there is none.
+///
+/// This is the first element of `kneedle_ranked`.
+///
+/// ```
+/// use kneedle::KneedleParams;
+///
+/// let data = [[0.0, 0.0], [1.0, 8.0], [2.0, 9.0], [3.0, 9.5], [4.0, 9.75], [5.0, 10.0]];
+/// let knee = kneedle::single_knee(&data, &KneedleParams::new().smoothing_window(0)).unwrap();
+/// assert_eq!(Some([1.0, 8.0]), knee);
+/// ```
+pub fn single_knee<T, I>(data: &[I], params: &KneedleParams) -> Result<Option<Point>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let knees = kneedle_ranked(data, params)?;
+    Ok(knees.first().map(|knee| [knee.x, knee.y]))
+}
+
+/// Runs the detection once for every sensitivity in `s_values` and returns the knee found by
+/// the most of them, with the fraction that agreed.
+///
+/// A knee that persists over a range of sensitivities is more likely a real feature of the curve
+/// than one that depends on the exact value of `S`. Ties go to the knee with the larger
+/// difference value. `params.s` is ignored. Returns `Ok(None)` when no sensitivity finds a knee.
+pub fn kneedle_sweep<T, I>(
+    data: &[I],
+    s_values: &[f64],
+    params: &KneedleParams,
+) -> Result<Option<SweepResult>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    //each knee found with the number of sensitivities that found it
+    let mut found: Vec<(KneeResult, usize)> = Vec::new();
+
+    for &s in s_values {
+        for knee in kneedle_detailed(data, &params.clone().sensitivity(s))? {
+            match found.iter_mut().find(|(seen, _)| seen.index == knee.index) {
+                Some((_, count)) => *count += 1,
+                None => found.push((knee, 1)),
+            }
+        }
+    }
+
+    let best = found.into_iter().max_by(|(a, a_count), (b, b_count)| {
+        a_count
+            .cmp(b_count)
+            .then(a.diff_value.abs().total_cmp(&b.diff_value.abs()))
+    });
+    Ok(best.map(|(knee, count)| SweepResult {
+        knee,
+        agreement: count as f64 / s_values.len() as f64,
+    }))
+}
+
+/// Searches for the smallest sensitivity that finds `desired` knees, and returns it with the
+/// knees it finds, for when the number of knees is known rather than `S`.
+///
+/// Fewer knees pass as `S` grows, so the sensitivity is doubled from 1 until no more than
+/// `desired` knees are found and the boundary then bisected. When no sensitivity gives exactly
+/// `desired` knees, as for more knees than the curve has at `S = 0`, the search settles on the
+/// sensitivity with the closest count, the smaller one on a tie. `params.s` is ignored.
+pub fn kneedle_target_count<T, I>(
+    data: &[I],
+    desired: usize,
+    params: &KneedleParams,
+) -> Result<(f64, Vec<KneeResult>), KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let detect = |s: f64| kneedle_detailed(data, &params.clone().sensitivity(s));
+
+    let mut low = (0.0, detect(0.0)?);
+    if low.1.len() <= desired {
+        return Ok(low);
+    }
+
+    //an upper bound finding no more than desired, low always finds more
+    let mut high = (1.0, detect(1.0)?);
+    while high.1.len() > desired && high.0 < MAX_TARGET_SENSITIVITY {
+        low = high;
+        let s = low.0 * 2.0;
+        high = (s, detect(s)?);
+    }
+    if high.1.len() > desired {
+        return Ok(high);
+    }
+
+    while high.0 - low.0 > TARGET_SENSITIVITY_TOLERANCE * high.0 {
+        let s = 0.5 * (low.0 + high.0);
+        let found = detect(s)?;
+        if found.len() > desired {
+            low = (s, found);
+        } else {
+            high = (s, found);
+        }
+    }
+
+    if low.1.len() - desired < desired - high.1.len() {
+        Ok(low)
+    } else {
+        Ok(high)
+    }
+}
+
+//largest sensitivity tried by kneedle_target_count, far past any threshold of a normalized curve
+const MAX_TARGET_SENSITIVITY: f64 = 1e6;
+
+//relative width the sensitivity of kneedle_target_count is bisected down to
+const TARGET_SENSITIVITY_TOLERANCE: f64 = 1e-6;
+
+/// Same as `kneedle_with`, with each knee refined to lie between the samples.
+///
+/// A parabola is fitted through the difference curve at the knee and its two neighbours, and the
+/// knee moved to its vertex, with y linearly interpolated from the neighbouring points. The
+/// returned x is usually not one of the input x-values. The difference curve used for the fit is
+/// that of the unsmoothed points, and the snapped knee is returned when it does not peak there.
+pub fn kneedle_interpolated<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<Point>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let rows = rows_for(data, params)?;
+
+    let mut workspace = KneedleWorkspace::new();
+    workspace.find_oriented_knees(&rows.points, None, params)?;
+    let knees = separate_knees(&rows.points, workspace.knees, params.min_separation);
+    let knees = select_knees(&rows.points, knees, params.selection);
+    Ok(knees
+        .into_iter()
+        .map(|knee| {
+            let [x, y] = interpolate_knee(&rows.points, knee.index, params.curve);
+            let linear = |value: f64| (value * core::f64::consts::LN_10).exp();
+            [
+                match params.x_scale {
+                    XScale::Linear => x,
+                    XScale::Log10 => linear(x),
+                },
+                match params.y_scale {
+                    YScale::Linear => y,
+                    YScale::Log10 => linear(y),
+                },
+            ]
+        })
+        .collect())
+}
+
+/// Same as `kneedle_with`, for data held as two parallel slices of x and y values.
+///
+/// The slices are zipped into a single flat buffer of points, no nested `Vec` is built.
+pub fn kneedle_xy(
+    x: &[f64],
+    y: &[f64],
+    params: &KneedleParams,
+) -> Result<Vec<(f64, f64)>, KneedleError> {
+    if x.len() != y.len() {
+        return Err(KneedleError::LengthMismatch {
+            x: x.len(),
+            y: y.len(),
+        });
+    }
+
+    let points: Vec<Point> = x
+        .iter()
+        .zip(y)
+        .map(|(&x, &y)| Point::from((x, y)))
+        .collect();
+    let knees = find_knees(&points, params)?;
+    Ok(knees
+        .into_iter()
+        .map(|knee| (x[knee.index], y[knee.index]))
+        .collect())
+}
+
+/// Same as `kneedle_with`, for points stored row by row in one flat buffer as
+/// `[x0, y0, x1, y1, ...]`.
+///
+/// The buffer is searched in place, which suits a contiguous `(n, 2)` matrix such as the
+/// `as_slice()` of a standard layout `ndarray::Array2`. Returns `NotTwoDimensional` when the
+/// length is odd.
+pub fn kneedle_interleaved<T: Float>(
+    data: &[T],
+    params: &KneedleParams,
+) -> Result<Vec<[T; 2]>, KneedleError> {
+    let (points, rest) = data.as_chunks::<2>();
+    if !rest.is_empty() {
+        return Err(KneedleError::NotTwoDimensional);
+    }
+
+    let knees = find_knees(points, params)?;
+    Ok(knees.into_iter().map(|knee| points[knee.index]).collect())
+}
+
+/// Same as `kneedle`, for an interleaved buffer of `f32` samples `[t0, v0, t1, v1, ...]` such as
+/// an ADC produces, searched in place and in `f32` throughout.
+///
+/// Returns `NotTwoDimensional` when the length is odd, see `kneedle_interleaved`.
+pub fn kneedle_interleaved_f32(
+    samples: &[f32],
+    s: f64,
+    smoothing_window: usize,
+    find_elbow: bool,
+) -> Result<Vec<(f32, f32)>, KneedleError> {
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(smoothing_window)
+        .elbow(find_elbow);
+    let knees = kneedle_interleaved(samples, &params)?;
+    Ok(knees.into_iter().map(<(f32, f32)>::from).collect())
+}
+
+/// Same as `kneedle`, with both the points and the knees as flat `[x0, y0, x1, y1, ...]` slices,
+/// a signature that crosses language boundaries such as `wasm-bindgen` as it is.
+///
+/// Input that cannot be searched, including a slice of odd length, gives an empty `Vec` just as a
+/// curve without a knee does. `kneedle_interleaved` tells the two apart.
+///
+/// ```
+/// let knees = kneedle::kneedle_flat(&[0.0, 0.0, 1.0, 8.0, 2.0, 9.0, 3.0, 9.5, 4.0, 10.0], 1.0, 0, false);
+/// assert_eq!(vec![1.0, 8.0], knees);
+/// ```
+pub fn kneedle_flat(xy: &[f64], s: f64, window: usize, find_elbow: bool) -> Vec<f64> {
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(window)
+        .elbow(find_elbow);
+    match kneedle_interleaved(xy, &params) {
+        Ok(knees) => knees.into_iter().flatten().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Same as `kneedle_with`, for data held as `(x, y)` tuples.
+pub fn kneedle_tuples(
+    data: &[(f64, f64)],
+    params: &KneedleParams,
+) -> Result<Vec<(f64, f64)>, KneedleError> {
+    let points: Vec<Point> = data.iter().copied().map(Point::from).collect();
+    let knees = find_knees(&points, params)?;
+    Ok(knees.into_iter().map(|knee| data[knee.index]).collect())
+}
+
+/// Same as `kneedle_with`, for points of any type that converts into `f64`, such as integer
+/// cluster counts or epoch numbers, returning the knee points as they are in `data`.
+///
+/// The points are converted once into a buffer of `f64`. Only types whose every value `f64`
+/// holds exactly implement `Into<f64>`: `i32`, `u32` and the smaller integers, and `f32`. Wider
+/// integers such as `i64` do not, as values past 2^53 would be rounded, and must be converted by
+/// the caller.
+///
+/// ```
+/// use kneedle::{Curve, KneedleParams};
+///
+/// let inertia: Vec<[u32; 2]> = vec![[1, 1000], [2, 400], [3, 200], [4, 150], [5, 120], [6, 100]];
+/// let params = KneedleParams::new().smoothing_window(0).curve(Curve::ConvexDecreasing);
+/// let knees = kneedle::kneedle_converted(&inertia, &params).unwrap();
+/// assert_eq!(vec![[3, 200]], knees);
+/// ```
+pub fn kneedle_converted<V>(
+    data: &[[V; 2]],
+    params: &KneedleParams,
+) -> Result<Vec<[V; 2]>, KneedleError>
+where
+    V: Into<f64> + Copy,
+{
+    let points: Vec<Point> = data.iter().map(|&[x, y]| [x.into(), y.into()]).collect();
+    let knees = find_knees(&points, params)?;
+    Ok(knees.into_iter().map(|knee| data[knee.index]).collect())
+}
+
+/// Estimates a smoothing window for `data` from its length and the noise on its y-values.
+///
+/// The noise level `sigma` is estimated from the median absolute second difference of y, which
+/// a smooth curve keeps close to zero, and compared with the mean change of y between two
+/// samples. Averaging `w` samples divides the noise by about `sqrt(w)`, so the window is
+/// `(sigma / step)^2`, the smallest that brings the noise down to the size of one step. It is kept
+/// between 1 and `n / 20` so that a large window never flattens the knee itself.
+pub fn auto_smoothing_window<T: Float, I: AsRef<[T]>>(data: &[I]) -> usize {
+    let n = data.len();
+    let max_window = (n / 20).max(1);
+    if n < 3 {
+        return 1;
+    }
+
+    let y = |i: usize| data[i].as_ref()[1].to_f64();
+    let mut second_differences: Vec<f64> = (1..n - 1)
+        .map(|i| (y(i + 1) - 2.0 * y(i) + y(i - 1)).abs())
+        .collect();
+    second_differences.sort_by(f64::total_cmp);
+    let median = second_differences[second_differences.len() / 2];
+
+    let (y_min, y_max) = (0..n)
+        .map(y)
+        .fold((f64::MAX, f64::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+    let step = (y_max - y_min) / (n - 1) as f64;
+    if step == 0.0 || !step.is_finite() {
+        return 1;
+    }
+
+    //the second difference of independent noise has variance 6 sigma^2, and the median of its
+    //absolute value is 0.6745 of its standard deviation
+    let sigma_squared = (median / 0.6745) * (median / 0.6745) / 6.0;
+    let window = sigma_squared / (step * step);
+
+    if window.is_nan() || window < 1.0 {
+        1
+    } else if window > max_window as f64 {
+        max_window
+    } else {
+        window as usize
+    }
+}
+
+/// Same as `kneedle_with`, with the smoothing window chosen by `auto_smoothing_window`.
+pub fn kneedle_auto_smooth<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<Vec<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let params = params.clone().smoothing_window(auto_smoothing_window(data));
+    kneedle_with(data, &params)
+}
+
+/// The elbow of a list of values sorted in either direction, such as term frequencies or cluster
+/// sizes ranked from largest to smallest, as its rank (the position in `values`) and value.
+///
+/// The values are searched as the curve `[rank, value]`, convex decreasing when they are sorted
+/// from largest to smallest and convex increasing otherwise, with sensitivity `s` and Gaussian
+/// smoothing over `smoothing_window` samples. Of several elbows the strongest is returned, as
+/// `single_knee` does, and `Ok(None)` when there is none.
+///
+/// ```
+/// let sizes = [500.0, 240.0, 90.0, 20.0, 15.0, 12.0, 10.0, 9.0, 8.0, 7.5, 7.0];
+/// assert_eq!(Some((3, 20.0)), kneedle::knee_of_sorted_values(&sizes, 1.0, 0).unwrap());
+/// ```
+pub fn knee_of_sorted_values(
+    values: &[f64],
+    s: f64,
+    smoothing_window: usize,
+) -> Result<Option<(usize, f64)>, KneedleError> {
+    let points: Vec<Point> = values
+        .iter()
+        .enumerate()
+        .map(|(rank, &value)| [rank as f64, value])
+        .collect();
+    let curve = match (values.first(), values.last()) {
+        (Some(first), Some(last)) if last < first => Curve::ConvexDecreasing,
+        _ => Curve::ConvexIncreasing,
+    };
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(smoothing_window)
+        .curve(curve);
+
+    let knees = kneedle_ranked(&points, &params)?;
+    Ok(knees.first().map(|knee| (knee.index, values[knee.index])))
+}
+
+/// The corner of a ROC or precision-recall style curve, the operating point where the rate on
+/// the y-axis stops rising quickly for the rate on the x-axis, `None` when it has no corner.
+///
+/// The points are searched as a concave increasing curve with sensitivity 1 and no smoothing, as
+/// their x-values are rarely evenly spaced and a curve pinned at `(0, 0)` and `(1, 1)` has no
+/// corner at either end. They are sorted by x first, so thresholds may be listed in either
+/// direction, and points sharing the same x are averaged into one. Of several corners the
+/// strongest is returned, as `single_knee` does.
+///
+/// ```
+/// let roc = [[0.0, 0.0], [0.05, 0.6], [0.1, 0.85], [0.3, 0.92], [0.6, 0.97], [1.0, 1.0]];
+/// assert_eq!(Some([0.1, 0.85]), kneedle::roc_knee(&roc).unwrap());
+/// ```
+pub fn roc_knee(points: &[Point]) -> Result<Option<Point>, KneedleError> {
+    let params = KneedleParams::new()
+        .smoothing_window(0)
+        .curve(Curve::ConcaveIncreasing)
+        .include_endpoints(false)
+        .sort_by_x(true)
+        .duplicate_x(DuplicateX::Average);
+    single_knee(points, &params)
+}
+
+/// The knees of a curve that falls to a minimum and rises again, such as a U-shaped cost curve,
+/// or rises to a maximum and falls again, one for each monotone arm on either side of it.
+///
+/// The curve is split at its global minimum when that lies strictly inside the data, at its
+/// global maximum otherwise, and the extremum belongs to both arms. Each arm is searched with
+/// `params`, its `curve` replaced by the shape of the arm, convex for a valley and concave for a
+/// hill, and its most prominent knee is returned, `None` for an arm without a knee or too short
+/// to search. `data` should be sorted by ascending x.
+///
+/// ```
+/// use kneedle::KneedleParams;
+///
+/// let data: Vec<[f64; 2]> = (0..=40).map(|i| [i as f64, ((i - 20) * (i - 20)) as f64]).collect();
+/// let (left, right) = kneedle::split_at_extremum(&data, &KneedleParams::new()).unwrap();
+/// assert_eq!(40.0 - left.unwrap()[0], right.unwrap()[0]);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn split_at_extremum<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<(Option<[T; 2]>, Option<[T; 2]>), KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+    if let Some((row, col)) = first_non_finite(data) {
+        return Err(KneedleError::NonFinite { row, col });
+    }
+
+    let y = |i: usize| data[i].as_ref()[1];
+    let (mut min, mut max) = (0, 0);
+    for i in 1..data.len() {
+        if y(i) < y(min) {
+            min = i;
+        }
+        if y(i) > y(max) {
+            max = i;
+        }
+    }
+    let (split, shapes) = if 0 < min && min < data.len() - 1 {
+        (min, (Curve::ConvexDecreasing, Curve::ConvexIncreasing))
+    } else {
+        (max, (Curve::ConcaveIncreasing, Curve::ConcaveDecreasing))
+    };
+
+    let arm_knee = |start: usize, arm: &[I], curve: Curve| -> Result<_, KneedleError> {
+        if arm.len() < 3 {
+            return Ok(None);
+        }
+        let knees = kneedle_ranked(arm, &params.clone().curve(curve))?;
+        Ok(knees.first().map(|knee| {
+            let row = data[start + knee.index].as_ref();
+            [row[0], row[1]]
+        }))
+    };
+    Ok((
+        arm_knee(0, &data[..=split], shapes.0)?,
+        arm_knee(split, &data[split..], shapes.1)?,
+    ))
+}
+
+/// Same as `kneedle`, but works out the shape of the curve from the data itself.
+///
+/// The curve is classified from the direction and the sign of the average second derivative of
+/// the smoothed and normalized data, and the detected `Curve` is returned alongside the knees.
+/// Returns `Ok(None)` when the data is too close to a straight line to classify.
+#[allow(clippy::type_complexity)]
+pub fn kneedle_auto<T, I>(
+    data: &[I],
+    s: f64,
+    smoothing_window: usize,
+) -> Result<Option<(Curve, Vec<[T; 2]>)>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let params = KneedleParams::new()
+        .sensitivity(s)
+        .smoothing_window(smoothing_window);
+    let smoothed_data = smooth(data, &params)?;
+    let normalized_data = minmax_normalize(smoothed_data)?;
+
+    let curve = match classify_curve(&normalized_data) {
+        Some(curve) => curve,
+        None => return Ok(None),
+    };
+
+    let params = params.curve(curve);
+    Ok(Some((curve, kneedle_with(data, &params)?)))
+}
+
+//the orientations detect tries, in the order ties between them are broken
+const ORIENTATIONS: [Curve; 4] = [
+    Curve::ConcaveIncreasing,
+    Curve::ConvexIncreasing,
+    Curve::ConcaveDecreasing,
+    Curve::ConvexDecreasing,
+];
+
+/// The knees of `data` searched in the orientation that suits it, without any shape hint: every
+/// `Curve` is tried with `params` and the one whose strongest knee has the most prominent peak
+/// on the difference curve wins, see `KneeResult::prominence`.
+///
+/// Unlike `kneedle_auto`, which classifies the curve by the sign of its average second
+/// derivative before searching it once, this judges each orientation by the peak it actually
+/// gives, so a curve that bends both ways still gets the orientation of its clearest knee. The
+/// `curve` of `params` is ignored, except that it is returned with no knees when no orientation
+/// finds any. Ties go to the first of concave increasing, convex increasing, concave decreasing
+/// and convex decreasing.
+///
+/// ```
+/// use kneedle::{Curve, KneedleParams};
+///
+/// let data = [[0.0, 100.0], [1.0, 40.0], [2.0, 20.0], [3.0, 15.0], [4.0, 10.0], [5.0, 5.0]];
+/// let (curve, knees) = kneedle::detect(&data, &KneedleParams::new().smoothing_window(0)).unwrap();
+/// assert_eq!(Curve::ConvexDecreasing, curve);
+/// assert_eq!(2, knees[0].index);
+/// ```
+pub fn detect<T, I>(
+    data: &[I],
+    params: &KneedleParams,
+) -> Result<(Curve, Vec<KneeResult>), KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let mut best: Option<(f64, Curve, Vec<KneeResult>)> = None;
+    for curve in ORIENTATIONS {
+        let knees = kneedle_detailed(data, &params.clone().curve(curve))?;
+        let Some(score) = knees
+            .iter()
+            .map(|knee| knee.prominence)
+            .max_by(f64::total_cmp)
+        else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|(best, _, _)| score > *best) {
+            best = Some((score, curve, knees));
+        }
+    }
+
+    Ok(match best {
+        Some((_, curve, knees)) => (curve, knees),
+        None => (params.curve, Vec::new()),
+    })
+}
+
+//checks shared by every entry point, the search needs at least one interior point
+fn validate<T: Float, I: AsRef<[T]>>(data: &[I]) -> Result<(), KneedleError> {
+    if data.is_empty() {
+        return Err(KneedleError::EmptyData);
+    }
+
+    if data[0].as_ref().len() != 2 {
+        return Err(KneedleError::NotTwoDimensional);
+    }
+
+    //before any row is indexed, a short row would otherwise panic in the preprocessing
+    if let Some(row) = data.iter().position(|row| row.as_ref().len() != 2) {
+        return Err(KneedleError::RaggedRows {
+            row,
+            expected: 2,
+            got: data[row].as_ref().len(),
+        });
+    }
+
+    if data.len() < 3 {
+        return Err(KneedleError::TooFewPoints(data.len()));
+    }
+
+    Ok(())
+}
+
+//where x lies between the smallest and largest finite x of data
+fn x_fraction<T: Float, I: AsRef<[T]>>(data: &[I], x: f64) -> f64 {
+    let (x_min, x_max) = data
+        .iter()
+        .map(|row| row.as_ref()[0].to_f64())
+        .filter(|x| x.is_finite())
+        .fold((f64::MAX, f64::MIN), |(x_min, x_max), x| {
+            (x_min.min(x), x_max.max(x))
+        });
+    if x_max > x_min {
+        (x - x_min) / (x_max - x_min)
+    } else {
+        0.0
+    }
+}
+
+fn find_knees<T, I>(data: &[I], params: &KneedleParams) -> Result<Vec<Knee<T>>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let mut workspace = KneedleWorkspace::new();
+    workspace.find_knees(data, None, None, params)?;
+    Ok(workspace.knees)
+}
+
+//non-maximum suppression: going from the strongest knee down, drop every knee closer than
+//min_separation in x to one already kept, the ones left stay in their original order
+fn separate_knees<T, I>(data: &[I], knees: Vec<Knee<T>>, min_separation: f64) -> Vec<Knee<T>>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    if min_separation.is_nan() || min_separation <= 0.0 || knees.len() < 2 {
+        return knees;
+    }
+
+    let mut order: Vec<usize> = (0..knees.len()).collect();
+    order.sort_by(|&a, &b| stronger(data, &knees[a], &knees[b]));
+
+    let x = |knee: &Knee<T>| data[knee.index].as_ref()[0].to_f64();
+
+    let mut kept = vec![false; knees.len()];
+    for &i in &order {
+        let near_kept = (0..knees.len())
+            .any(|j| kept[j] && (x(&knees[i]) - x(&knees[j])).abs() < min_separation);
+        kept[i] = !near_kept;
+    }
+
+    knees
+        .into_iter()
+        .zip(kept)
+        .filter_map(|(knee, kept)| kept.then_some(knee))
+        .collect()
+}
+
+//the ordering of kneedle_ranked, the larger magnitude of the difference curve first and the
+//smaller x of two as large
+fn stronger<T: Float, I: AsRef<[T]>>(data: &[I], a: &Knee<T>, b: &Knee<T>) -> Ordering {
+    let x = |knee: &Knee<T>| data[knee.index].as_ref()[0].to_f64();
+    b.diff_value
+        .abs()
+        .to_f64()
+        .total_cmp(&a.diff_value.abs().to_f64())
+        .then(x(a).total_cmp(&x(b)))
+}
+
+fn select_knees<T, I>(data: &[I], mut knees: Vec<Knee<T>>, selection: Selection) -> Vec<Knee<T>>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    match selection {
+        Selection::All => {}
+        Selection::FirstMostProminent => {
+            if let Some(strongest) =
+                (0..knees.len()).min_by(|&a, &b| stronger(data, &knees[a], &knees[b]))
+            {
+                knees.swap(0, strongest);
+                knees.truncate(1);
+            }
+        }
+        Selection::Last => {
+            let skipped = knees.len().saturating_sub(1);
+            knees.drain(..skipped);
+        }
+    }
+    knees
+}
+
+//apply the sorting and duplicate handling of params to a copy of data, None when data can be
+//searched as it is
+fn clean_rows<T, I>(data: &[I], params: &KneedleParams) -> Result<Option<Rows<T>>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    let descending = is_descending(data, params.x_order);
+
+    if !descending && keeps_rows(params) {
+        if let Some((row, col)) = first_non_finite(data) {
+            return Err(KneedleError::NonFinite { row, col });
+        }
+        if let Some(row) = first_duplicate_x(data) {
+            return Err(KneedleError::DuplicateX { row });
+        }
+        if let Some(tolerance) = params.monotonic_tolerance {
+            if let Some(index) = first_non_monotonic(data, tolerance) {
+                return Err(KneedleError::NonMonotonic { index });
+            }
+        }
+        return Ok(None);
+    }
+
+    let mut rows = Rows::new(data);
+    rows.filter_non_finite(params.non_finite)?;
+    if params.x_scale == XScale::Log10 {
+        rows.log10_x()?;
+    }
+    if params.y_scale == YScale::Log10 {
+        rows.log10_y()?;
+    }
+    if descending {
+        rows.reverse();
+    }
+    if params.sort_by_x {
+        rows.sort_by_x()?;
+    }
+    rows.dedup_x(params.duplicate_x)?;
+    rows.trim_flat(
+        params.trim_flat_prefix,
+        params.trim_flat_suffix,
+        params.flat_tolerance,
+    );
+
+    if rows.points.len() < 3 {
+        return Err(KneedleError::TooFewPoints(rows.points.len()));
+    }
+    if let Some(tolerance) = params.monotonic_tolerance {
+        if let Some(i) = first_non_monotonic(&rows.points, tolerance) {
+            return Err(KneedleError::NonMonotonic {
+                index: rows.origin[i],
+            });
+        }
+    }
+
+    Ok(Some(rows))
+}
+
+//refine the knee at index k of points to the vertex of the parabola through the difference curve
+//at k and its two neighbours, keeping points[k] when the parabola does not peak between them or
+//when k is an endpoint without a neighbour on one side
+fn interpolate_knee<T: Float>(points: &[[T; 2]], k: usize, curve: Curve) -> Point {
+    let point = |i: usize| [points[i][0].to_f64(), points[i][1].to_f64()];
+    let snapped = point(k);
+    if k == 0 || k + 1 == points.len() {
+        return snapped;
+    }
+
+    let (mut x_min, mut x_max) = (f64::MAX, f64::MIN);
+    let (mut y_min, mut y_max) = (f64::MAX, f64::MIN);
+    for i in 0..points.len() {
+        let [x, y] = point(i);
+        x_min = x_min.min(x);
+        x_max = x_max.max(x);
+        y_min = y_min.min(y);
+        y_max = y_max.max(y);
+    }
+    if x_min == x_max || y_min == y_max {
+        return snapped;
+    }
+
+    //the difference curve of step 3, with x flipped for decreasing curves as the search does
+    let difference = |[x, y]: Point| {
+        let x = (x - x_min) / (x_max - x_min);
+        let y = (y - y_min) / (y_max - y_min);
+        if curve.is_decreasing() {
+            y + x
+        } else {
+            y - x
+        }
+    };
+
+    let [p0, p1, p2] = [point(k - 1), snapped, point(k + 1)];
+    let [d0, d1, d2] = [p0, p1, p2].map(difference);
+    let [x0, x1, x2] = [p0[0], p1[0], p2[0]];
+
+    let denominator = (x0 - x1) * (x0 - x2) * (x1 - x2);
+    let a = (x2 * (d1 - d0) + x1 * (d0 - d2) + x0 * (d2 - d1)) / denominator;
+    let b = (x2 * x2 * (d0 - d1) + x1 * x1 * (d2 - d0) + x0 * x0 * (d1 - d2)) / denominator;
+
+    //a knee is a maximum of the difference curve and an elbow a minimum
+    let peaks = if curve.is_convex() { a > 0.0 } else { a < 0.0 };
+    if !peaks || !a.is_finite() || !b.is_finite() {
+        return snapped;
+    }
+
+    let x = (-b / (2.0 * a)).clamp(x0.min(x2), x0.max(x2));
+    let (from, to) = if (x - x0).abs() <= (x1 - x0).abs() {
+        (p0, p1)
+    } else {
+        (p1, p2)
+    };
+    let y = from[1] + (to[1] - from[1]) * (x - from[0]) / (to[0] - from[0]);
+    [x, y]
+}
+
+//the signed drop of step 5 from a candidate of the difference curve to its threshold, down for
+//knees and up for elbows
+fn threshold_step<T: Float>(curve: &[[T; 2]], params: &KneedleParams) -> T {
+    let step = match params.threshold {
+        ThresholdMode::Relative => mean_x_step(curve) * T::from_f64(params.s),
+        ThresholdMode::Absolute(drop) => T::from_f64(drop),
+        ThresholdMode::NormalizedSensitivity => x_span(curve) * T::from_f64(params.s),
+    };
+    if params.curve.is_convex() {
+        step
+    } else {
+        -step
+    }
+}
+
+//steps 4 and 6 of the paper over a difference curve, with the signed threshold step of step 5.
+//the knees found replace the contents of local_min_max, candidate_indices is scratch space
+fn threshold_knees<T: Float, R: AsRef<[T]>>(
+    normalized_data: &[R],
+    step: T,
+    find_elbow: bool,
+    include_endpoints: bool,
+    extremum_tolerance: T,
+    candidate_indices: &mut Vec<usize>,
+    local_min_max: &mut Vec<Knee<T>>,
+) {
+    //find candidate indices (this is step 4 in the paper)
+    find_candidate_indices(
+        normalized_data,
+        find_elbow,
+        include_endpoints,
+        extremum_tolerance,
+        candidate_indices,
+    );
+
+    threshold_candidates(
+        normalized_data,
+        step,
+        find_elbow,
+        candidate_indices,
+        local_min_max,
+    );
+}
+
+//step 6 of the paper over candidate_indices, which must be ascending
+fn threshold_candidates<T: Float, R: AsRef<[T]>>(
+    normalized_data: &[R],
+    step: T,
+    find_elbow: bool,
+    candidate_indices: &[usize],
+    local_min_max: &mut Vec<Knee<T>>,
+) {
+    let datasize = normalized_data.len();
+    local_min_max.clear();
+
+    //without a local extremum, as on a difference curve that only rises or only falls, nothing
+    //can be confirmed and the curve has no knee, whatever the threshold
+    if candidate_indices.is_empty() {
+        return;
+    }
+
+    //check each candidate to see if it is a real elbow/knee (this is step 6 in the paper). the
+    //threshold is set at every candidate, and reset when the curve passes a local min/max and
+    //turns back before crossing it, until the next candidate is reached. a candidate as high as
+    //the one before it, with no crossing between them, ties with it and the tie goes to the
+    //earlier one, which keeps the threshold, whether or not the curve turned back in between
+    let mut candidates = candidate_indices.iter().peekable();
+    let mut threshold: Option<(usize, T)> = None;
+    let mut reset: Option<usize> = None;
+    let knee = |index: usize| Knee {
+        index,
+        diff_value: normalized_data[index].as_ref()[1],
+        threshold: normalized_data[index].as_ref()[1] + step,
+        prominence: prominence(normalized_data, index, find_elbow),
+    };
+
+    for j in 0..datasize {
+        let cur = normalized_data[j].as_ref()[1];
+
+        if candidates.peek() == Some(&&j) {
+            candidates.next();
+            //the last point has nothing after it to cross the threshold, being a candidate is
+            //as much as it can show
+            let earlier = threshold.take().map(|(i, _)| i).or(reset.take());
+            let index = match earlier {
+                Some(earlier) if normalized_data[earlier].as_ref()[1] == cur => earlier,
+                _ => j,
+            };
+            if j == datasize - 1 {
+                local_min_max.push(knee(index));
+            } else {
+                threshold = Some((index, cur + step));
+            }
+            continue;
+        }
+
+        if let Some((candidate_index, value)) = threshold {
+            let prev = normalized_data[j - 1].as_ref()[1];
+            if (find_elbow && cur > value) || (!find_elbow && cur < value) {
+                local_min_max.push(knee(candidate_index));
+                threshold = None;
+            } else if (find_elbow && cur < prev) || (!find_elbow && cur > prev) {
+                reset = Some(candidate_index);
+                threshold = None;
+            }
+        }
+    }
+}
+
+//topographic prominence of the extremum at index i of a difference curve: its height above the
+//higher of the lowest points on either side before the curve rises past it again, mirrored for
+//elbows, which are minima. it is not capped, see KneeResult::prominence for its range
+fn prominence<T: Float, R: AsRef<[T]>>(normalized_data: &[R], i: usize, find_elbow: bool) -> T {
+    let height = |j: usize| {
+        if find_elbow {
+            -normalized_data[j].as_ref()[1]
+        } else {
+            normalized_data[j].as_ref()[1]
+        }
+    };
+    let peak = height(i);
+    let lowest = |indices: &mut dyn Iterator<Item = usize>| {
+        indices
+            .map(height)
+            .take_while(|&h| h <= peak)
+            .fold(peak, |low, h| low.min(h))
+    };
+
+    let left = lowest(&mut (0..i).rev());
+    let right = lowest(&mut (i + 1..normalized_data.len()));
+    peak - left.max(right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx_eq::assert_approx_eq;
+
+    #[test]
+    fn it_works() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+
+        let knee_points = kneedle(&test_data, 1.0, 1, false).unwrap();
+
+        assert_eq!(1, knee_points.len());
+        assert_approx_eq!(0.2, knee_points[0][0]);
+        assert_approx_eq!(0.75, knee_points[0][1]);
+    }
+
+    #[test]
+    fn flat_prefix_trimmed() {
+        let curve = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        //a warm-up of 5 points at the starting level, then the it_works curve shortly after
+        let mut test_data: Vec<Point> = (0..5).map(|i| [i as f64 - 5.0, 0.0]).collect();
+        test_data.extend(curve.iter().map(|&[x, y]| [x, y]));
+        let params = KneedleParams::new().smoothing_window(1);
+
+        //the bend out of the warm-up shadows the knee
+        assert!(!kneedle_with(&test_data, &params)
+            .unwrap()
+            .contains(&[0.2, 0.75]));
+
+        let trimmed = params.clone().trim_flat_prefix(true);
+        assert_eq!(
+            vec![[0.2, 0.75]],
+            kneedle_with(&test_data, &trimmed).unwrap()
+        );
+        assert_eq!(7, kneedle_detailed(&test_data, &trimmed).unwrap()[0].index);
+
+        //a level tail after the curve, and a warm-up that wobbles within the tolerance
+        test_data.extend((1..=5).map(|i| [1.0 + 0.1 * i as f64, 1.0]));
+        test_data[1][1] = 0.01;
+        let trimmed = trimmed.trim_flat_suffix(true).flat_tolerance(0.02);
+        assert_eq!(
+            vec![[0.2, 0.75]],
+            kneedle_with(&test_data, &trimmed).unwrap()
+        );
+    }
+
+    #[test]
+    fn try_malformed() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+        ];
+        assert_eq!(
+            Some(vec![[0.2, 0.75]]),
+            try_kneedle(&test_data, 1.0, 0, false)
+        );
+
+        let empty: [Point; 0] = [];
+        assert_eq!(None, try_kneedle(&empty, 1.0, 1, false));
+        assert_eq!(None, try_kneedle(&test_data[..2], 1.0, 1, false));
+        let ragged = vec![vec![0.0, 0.0], vec![1.0], vec![2.0, 1.0], vec![3.0, 1.5]];
+        assert_eq!(None, try_kneedle(&ragged, 1.0, 1, false));
+        let mut non_finite = test_data;
+        non_finite[3][1] = f64::NAN;
+        assert_eq!(None, try_kneedle(&non_finite, 1.0, 1, false));
+        let mut repeated = test_data;
+        repeated[2][0] = repeated[1][0];
+        assert!(try_kneedle(&repeated, 1.0, 1, false)
+            .unwrap_or_default()
+            .is_empty());
+    }
+
+    #[test]
+    fn normalized_sensitivity() {
+        //a hump with a shallow second bump on its way down, sampled at two densities, so the
+        //dip between the two peaks is the same on both difference curves
+        let curve = |n: usize| -> Vec<Point> {
+            (0..n)
+                .map(|i| {
+                    let x = i as f64 / (n - 1) as f64;
+                    let bump = 0.04 * (-((x - 0.7) / 0.06).powi(2)).exp();
+                    [x, x + 0.3 * (core::f64::consts::PI * x).sin() + bump]
+                })
+                .collect()
+        };
+        let (sparse, dense) = (curve(41), curve(401));
+        let params = KneedleParams::new().smoothing_window(0);
+        let counts = |params: &KneedleParams| {
+            [&sparse, &dense].map(|data| kneedle_with(data, params).unwrap().len())
+        };
+
+        //the classic step shrinks with the number of rows, so only the dense curve keeps both
+        assert_eq!([1, 2], counts(&params));
+
+        let normalized = params.threshold(ThresholdMode::NormalizedSensitivity);
+        assert_eq!([2, 2], counts(&normalized.clone().sensitivity(0.01)));
+        assert_eq!([1, 1], counts(&normalized.clone().sensitivity(0.02)));
+        assert_approx_eq!(
+            -0.02,
+            kneedle_trace(&sparse, &normalized.sensitivity(0.02))
+                .unwrap()
+                .step
+        );
+    }
+
+    #[test]
+    fn threshold_step_recorded() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        let params = KneedleParams::new().smoothing_window(1);
+
+        //the normalized x-values span 1 in 9 steps, knees are confirmed below their height
+        let trace = kneedle_trace(&test_data, &params).unwrap();
+        assert_approx_eq!(-1.0 / 9.0, trace.step);
+
+        let knees = kneedle_detailed(&test_data, &params).unwrap();
+        assert_eq!(1, knees.len());
+        assert_approx_eq!(knees[0].diff_value + trace.step, knees[0].threshold);
+        //the decision can be replayed on the trace: the curve falls below it after the knee
+        assert!(trace.difference[knees[0].index + 1..]
+            .iter()
+            .any(|point| point[1] < knees[0].threshold));
+    }
+
+    #[test]
+    fn fraction() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        let params = KneedleParams::new().smoothing_window(1);
+        let fractions = kneedle_fraction(&test_data, &params).unwrap();
+        assert_eq!(1, fractions.len());
+        assert_approx_eq!(0.2, fractions[0]);
+
+        //the same curve stretched and shifted along x has its knee at the same fraction
+        let moved: Vec<Point> = test_data
+            .iter()
+            .map(|&[x, y]| [1000.0 * x - 50.0, y])
+            .collect();
+        assert_approx_eq!(0.2, kneedle_fraction(&moved, &params).unwrap()[0]);
+
+        assert_eq!(0.0, x_fraction(&[[3.0, 1.0], [3.0, 2.0]], 3.0));
+        assert_eq!(
+            0.5,
+            x_fraction(&[[f64::NAN, 0.0], [2.0, 1.0], [4.0, 2.0]], 3.0)
+        );
+    }
+
+    #[test]
+    fn mean_x_step() {
+        let data: Vec<Vec<f64>> = [0.0, 0.1, 0.2, 0.35, 0.45, 0.55, 0.675, 0.775, 0.875, 1.0]
+            .iter()
+            .map(|&x| vec![x, 0.0])
+            .collect();
+
+        assert_approx_eq!(1.0 / 9.0, super::mean_x_step(&data));
+
+        assert_eq!(0.0, super::mean_x_step(&data[..1]));
+        assert_eq!(0.0, super::mean_x_step::<f64, Vec<f64>>(&[]));
+    }
+
+    #[test]
+    fn it_works_f32() {
+        let test_data: [[f32; 2]; 10] = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+
+        let knee_points = kneedle(&test_data, 1.0, 1, false).unwrap();
+
+        assert_eq!(1, knee_points.len());
+        assert_eq!([0.2, 0.75], knee_points[0]);
+    }
+
+    #[test]
+    fn indices() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+
+        let knee_indices = kneedle_indices(&test_data, 1.0, 1, false).unwrap();
+        assert_eq!(vec![2], knee_indices);
+
+        let knee_points = kneedle(&test_data, 1.0, 1, false).unwrap();
+        assert_eq!(knee_points, vec![test_data[knee_indices[0]]]);
+    }
+
+    #[test]
+    fn params() {
+        let test_data = [
+            [0.0, 0.0],
+            [1.0, 60.0],
+            [2.0, 80.0],
+            [3.0, 85.0],
+            [4.0, 90.0],
+            [5.0, 95.0],
+            [6.0, 96.0],
+            [7.0, 97.0],
+            [8.0, 98.0],
+            [9.0, 99.0],
+        ];
+
+        let defaults = KneedleParams::new();
+        assert_eq!(1.0, defaults.s);
+        assert_eq!(3, defaults.smoothing_window);
+        assert_eq!(Curve::ConcaveIncreasing, defaults.curve);
+
+        let params = KneedleParams::new()
+            .sensitivity(1.0)
+            .smoothing_window(1)
+            .elbow(false);
+        assert_eq!(
+            kneedle(&test_data, 1.0, 1, false).unwrap(),
+            kneedle_with(&test_data, &params).unwrap()
+        );
+    }
+
+    #[test]
+    fn auto() {
+        let concave_increasing = [
+            [0.0, 0.0],
+            [1.0, 60.0],
+            [2.0, 80.0],
+            [3.0, 85.0],
+            [4.0, 90.0],
+            [5.0, 95.0],
+            [6.0, 96.0],
+            [7.0, 97.0],
+            [8.0, 98.0],
+            [9.0, 99.0],
+        ];
+        let (curve, knee_points) = kneedle_auto(&concave_increasing, 1.0, 1).unwrap().unwrap();
+        assert_eq!(Curve::ConcaveIncreasing, curve);
+        assert_eq!(vec![[2.0, 80.0]], knee_points);
+
+        let convex_decreasing = [
+            [0.0, 100.0],
+            [1.0, 40.0],
+            [2.0, 20.0],
+            [3.0, 15.0],
+            [4.0, 10.0],
+            [5.0, 5.0],
+            [6.0, 4.0],
+            [7.0, 3.0],
+            [8.0, 2.0],
+            [9.0, 1.0],
+        ];
+        let (curve, knee_points) = kneedle_auto(&convex_decreasing, 1.0, 1).unwrap().unwrap();
+        assert_eq!(Curve::ConvexDecreasing, curve);
+        assert_eq!(vec![[2.0, 20.0]], knee_points);
+
+        let convex_increasing = [
+            [0.0, 1.0],
+            [1.0, 2.0],
+            [2.0, 3.0],
+            [3.0, 4.0],
+            [4.0, 5.0],
+            [5.0, 10.0],
+            [6.0, 15.0],
+            [7.0, 20.0],
+            [8.0, 40.0],
+            [9.0, 100.0],
+        ];
+        let (curve, _) = kneedle_auto(&convex_increasing, 1.0, 1).unwrap().unwrap();
+        assert_eq!(Curve::ConvexIncreasing, curve);
+
+        let concave_decreasing = [
+            [0.0, 99.0],
+            [1.0, 98.0],
+            [2.0, 97.0],
+            [3.0, 96.0],
+            [4.0, 95.0],
+            [5.0, 90.0],
+            [6.0, 85.0],
+            [7.0, 80.0],
+            [8.0, 60.0],
+            [9.0, 0.0],
+        ];
+        let (curve, _) = kneedle_auto(&concave_decreasing, 1.0, 1).unwrap().unwrap();
+        assert_eq!(Curve::ConcaveDecreasing, curve);
+
+        let linear: Vec<Point> = (0..10).map(|i| [i as f64, 2.0 * i as f64]).collect();
+        assert_eq!(None, kneedle_auto(&linear, 1.0, 1).unwrap());
+    }
+
+    #[test]
+    fn detailed() {
+        let test_data = [
+            [0.0, 100.0],
+            [1.0, 40.0],
+            [2.0, 20.0],
+            [3.0, 15.0],
+            [4.0, 10.0],
+            [5.0, 5.0],
+            [6.0, 4.0],
+            [7.0, 3.0],
+            [8.0, 2.0],
+            [9.0, 1.0],
+        ];
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(Curve::ConvexDecreasing);
+
+        let knees = kneedle_detailed(&test_data, &params).unwrap();
+        assert_eq!(1, knees.len());
+        assert_eq!(2, knees[0].index);
+        assert_approx_eq!(2.0, knees[0].x);
+        assert_approx_eq!(20.0, knees[0].y);
+
+        let normalized_data = prepare(
+            &flip_x(&test_data),
+            &KneedleParams::new().smoothing_window(1),
+        )
+        .unwrap();
+        assert_approx_eq!(normalized_data[9 - 2][1], knees[0].diff_value);
+    }
+
+    #[test]
+    fn difference() {
+        let test_data = [
+            [0.0, 0.0],
+            [1.0, 60.0],
+            [2.0, 80.0],
+            [3.0, 85.0],
+            [4.0, 90.0],
+            [5.0, 95.0],
+            [6.0, 96.0],
+            [7.0, 97.0],
+            [8.0, 98.0],
+            [9.0, 99.0],
+        ];
+
+        let curve = difference_curve(&test_data, 1).unwrap();
+        assert_eq!(test_data.len(), curve.len());
+        assert_approx_eq!(0.0, curve[0][0]);
+        assert_approx_eq!(1.0, curve[9][0]);
+        assert_approx_eq!(0.0, curve[0][1]);
+        assert_approx_eq!(0.0, curve[9][1]);
+
+        let knee = kneedle_detailed(&test_data, &KneedleParams::new().smoothing_window(1)).unwrap();
+        assert_approx_eq!(curve[knee[0].index][1], knee[0].diff_value);
+    }
+
+    #[test]
+    fn difference_area() {
+        let line: Vec<Point> = (0..10).map(|i| [i as f64, 3.0 * i as f64 + 1.0]).collect();
+        assert!(difference_curve_area(&line, 1).unwrap() < 1e-9);
+
+        let sharp: Vec<Point> = (0..10)
+            .map(|i| [i as f64, if i == 0 { 0.0 } else { 99.0 + i as f64 }])
+            .collect();
+        let area = difference_curve_area(&sharp, 0).unwrap();
+        assert!(area > 0.4 && area < 0.5, "{}", area);
+
+        //an elbow bends the other way and counts the same
+        let elbow: Vec<Point> = sharp.iter().map(|&[x, y]| [9.0 - x, 109.0 - y]).collect();
+        assert_approx_eq!(area, difference_curve_area(&elbow, 0).unwrap());
+        assert!(difference_curve_area(&sharp, 1).unwrap() < area);
+    }
+
+    #[test]
+    fn normalized_without_difference() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new();
+
+        let normalized = smooth_and_normalize(&test_data, &params).unwrap();
+        assert_eq!(test_data.len(), normalized.len());
+        for d in 0..2 {
+            let values = normalized.iter().map(|row| row[d]);
+            assert!(values.clone().all(|value| (0.0..=1.0).contains(&value)));
+            assert_eq!(0.0, values.clone().fold(f64::MAX, f64::min));
+            assert_eq!(1.0, values.fold(f64::MIN, f64::max));
+        }
+
+        //the difference curve is the same with y - x taken
+        let difference = difference_curve(&test_data, params.smoothing_window).unwrap();
+        for (row, difference) in normalized.iter().zip(&difference) {
+            assert_eq!([row[0], row[1] - row[0]], *difference);
+        }
+    }
+
+    #[test]
+    fn prepared() {
+        let test_data = [
+            [0.0, 0.0],
+            [1.0, 60.0],
+            [2.0, 80.0],
+            [3.0, 85.0],
+            [4.0, 90.0],
+            [5.0, 95.0],
+            [6.0, 96.0],
+            [7.0, 97.0],
+            [8.0, 98.0],
+            [9.0, 99.0],
+        ];
+
+        //the search alone finds the same knee on the curve the full detection prepares
+        let curve = difference_curve(&test_data, 1).unwrap();
+        assert_eq!(
+            vec![curve[2]],
+            kneedle_prepared(&curve, 1.0, false).unwrap()
+        );
+        assert_eq!(
+            vec![[2.0, 80.0]],
+            kneedle(&test_data, 1.0, 1, false).unwrap()
+        );
+
+        //and no elbow, as the difference curve has no minimum
+        assert!(kneedle_prepared(&curve, 1.0, true).unwrap().is_empty());
+
+        let mut with_nan = curve.clone();
+        with_nan[4][1] = f64::NAN;
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 4, col: 1 }),
+            kneedle_prepared(&with_nan, 1.0, false)
+        );
+    }
+
+    #[test]
+    fn ranked() {
+        //a weak bend at x = 2 followed by a much sharper one at x = 12, which smoothing moves to 13
+        let mut test_data = Vec::new();
+        for x in 0..25 {
+            let y = match x {
+                0..=2 => 10.0 * x as f64,
+                3..=10 => 20.0 + 0.5 * (x - 2) as f64,
+                11..=12 => 24.0 + 35.0 * (x - 10) as f64,
+                _ => 94.0 + 0.5 * (x - 12) as f64,
+            };
+            test_data.push([x as f64, y]);
+        }
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let knees = kneedle_ranked(&test_data, &params).unwrap();
+        assert_eq!(2, knees.len());
+        assert_approx_eq!(13.0, knees[0].x);
+        assert_approx_eq!(2.0, knees[1].x);
+        assert!(knees[0].diff_value > knees[1].diff_value);
+    }
+
+    #[test]
+    fn single_knee() {
+        let test_data: Vec<Point> = (0..25)
+            .map(|x| {
+                let y = match x {
+                    0..=2 => 10.0 * x as f64,
+                    3..=10 => 20.0 + 0.5 * (x - 2) as f64,
+                    11..=12 => 24.0 + 35.0 * (x - 10) as f64,
+                    _ => 94.0 + 0.5 * (x - 12) as f64,
+                };
+                [x as f64, y]
+            })
+            .collect();
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let knee = super::single_knee(&test_data, &params).unwrap().unwrap();
+        assert_approx_eq!(13.0, knee[0]);
+
+        let line: Vec<Point> = (0..10).map(|x| [x as f64, x as f64]).collect();
+        assert_eq!(None, super::single_knee(&line, &params).unwrap());
+    }
+
+    #[test]
+    fn interpolated() {
+        //y = -1/x + 5 on [0.1, 1] has its knee at sqrt(0.1), between the samples
+        let test_data: Vec<Point> = (0..11)
+            .map(|i| {
+                let x = 0.1 + 0.09 * i as f64;
+                [x, -1.0 / x + 5.0]
+            })
+            .collect();
+        let params = KneedleParams::new().smoothing_window(0);
+        let analytic = 0.1f64.sqrt();
+
+        let snapped = super::single_knee(&test_data, &params).unwrap().unwrap();
+        let interpolated = kneedle_interpolated(&test_data, &params).unwrap();
+
+        assert_eq!(1, interpolated.len());
+        assert!((interpolated[0][0] - analytic).abs() < (snapped[0] - analytic).abs() / 2.0);
+        assert!(interpolated[0][1] > snapped[1]);
+
+        //a knee on the first row has no parabola to fit and stays where it is
+        let test_data = [
+            [0.0, 1.0],
+            [1.0, 0.0],
+            [2.0, 0.1],
+            [3.0, 0.2],
+            [4.0, 0.3],
+            [5.0, 0.35],
+            [6.0, 0.4],
+        ];
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .include_endpoints(true);
+        assert_eq!(0, kneedle_detailed(&test_data, &params).unwrap()[0].index);
+        assert_eq!(
+            [0.0, 1.0],
+            kneedle_interpolated(&test_data, &params).unwrap()[0]
+        );
+    }
+
+    #[test]
+    fn smooth_y_only() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new().curve(Curve::ConvexDecreasing);
+
+        //the bumpy data is spaced unevenly, keeping its x-values changes the difference curve but
+        //not where the knee is
+        let knees = kneedle_detailed(&test_data, &params).unwrap();
+        let y_only = kneedle_detailed(&test_data, &params.smooth_y_only(true)).unwrap();
+        assert_eq!(1, y_only.len());
+        assert_eq!(knees[0].index, y_only[0].index);
+        assert_ne!(knees[0].diff_value, y_only[0].diff_value);
+    }
+
+    #[test]
+    fn smoothing_by_x() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new()
+            .curve(Curve::ConvexDecreasing)
+            .smoothing(SmoothingKind::GaussianDistance { bandwidth: 1.0 });
+
+        let knees = kneedle_detailed(&test_data, &params).unwrap();
+        let normalized_data =
+            minmax_normalize(gaussian_smooth_by_x(&flip_x(&test_data), 1.0).unwrap()).unwrap();
+        for knee in knees {
+            let row = &normalized_data[test_data.len() - 1 - knee.index];
+            assert_approx_eq!(row[1] - row[0], knee.diff_value);
+        }
+    }
+
+    #[test]
+    fn median_smoothing() {
+        let clean: Vec<Point> = (0..40)
+            .map(|i| {
+                let x = i as f64;
+                [x, 100.0 * (1.0 - (-x / 4.0).exp())]
+            })
+            .collect();
+        let mut spiky = clean.clone();
+        spiky[25][1] += 30.0;
+
+        let gaussian = KneedleParams::new();
+        let median = gaussian
+            .clone()
+            .smoothing(SmoothingKind::Median { window: 3 });
+
+        //the Gaussian kernel spreads the spike into a bump that passes for a second knee
+        let knees = kneedle_with(&clean, &gaussian).unwrap();
+        assert_eq!(vec![clean[10]], knees);
+        assert_eq!(
+            vec![spiky[10], spiky[22]],
+            kneedle_with(&spiky, &gaussian).unwrap()
+        );
+
+        let knees = kneedle_with(&clean, &median).unwrap();
+        assert_eq!(vec![clean[9]], knees);
+        assert_eq!(knees, kneedle_with(&spiky, &median).unwrap());
+    }
+
+    #[test]
+    fn savitzky_golay_smoothing() {
+        //a steep straight line up to a sharp corner at x = 8, then nearly flat
+        let test_data: Vec<Point> = (0..40)
+            .map(|i| {
+                let y = if i <= 8 {
+                    20.0 * i as f64
+                } else {
+                    160.0 + 0.5 * (i - 8) as f64
+                };
+                [i as f64, y]
+            })
+            .collect();
+        let params = KneedleParams::new();
+
+        let gaussian = kneedle_with(&test_data, &params).unwrap();
+        let savitzky_golay = kneedle_with(
+            &test_data,
+            &params.smoothing(SmoothingKind::SavitzkyGolay {
+                window: 7,
+                order: 2,
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(1, gaussian.len());
+        assert_eq!(1, savitzky_golay.len());
+        assert!((savitzky_golay[0][0] - 8.0).abs() < (gaussian[0][0] - 8.0).abs());
+    }
+
+    #[test]
+    fn prominence() {
+        //a sharp corner and a gentle bend between the same end points
+        let sharp_data: Vec<Point> = (0..=20)
+            .map(|i| [i as f64, if i <= 10 { 0.0 } else { (i - 10) as f64 }])
+            .collect();
+        let gentle_data: Vec<Point> = (0..=20)
+            .map(|i| [i as f64, 10.0 * (i as f64 / 20.0).powi(2)])
+            .collect();
+        let params = KneedleParams::new().smoothing_window(0).elbow(true);
+
+        let sharp = kneedle_ranked(&sharp_data, &params).unwrap();
+        let gentle = kneedle_ranked(&gentle_data, &params).unwrap();
+
+        assert_approx_eq!(0.5, sharp[0].prominence);
+        assert!(sharp[0].prominence > gentle[0].prominence);
+        assert!(gentle[0].prominence > 0.0);
+
+        //z-scores are not bounded, the sharp corner stands out by more than 1 and still ranks
+        //above the gentle bend rather than being cut off
+        let zscore = params.normalization(Normalization::ZScore);
+        let sharp = kneedle_ranked(&sharp_data, &zscore).unwrap();
+        let gentle = kneedle_ranked(&gentle_data, &zscore).unwrap();
+        assert!(sharp[0].prominence > 1.0);
+        assert!(sharp[0].prominence > gentle[0].prominence);
+    }
+
+    #[test]
+    fn target_count() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new()
+            .curve(Curve::ConvexDecreasing)
+            .smoothing_window(1);
+
+        let (s, knees) = kneedle_target_count(&test_data, 1, &params).unwrap();
+        assert_eq!(1, knees.len());
+        assert_eq!(26, knees[0].index);
+        assert!(s > 0.03 && s < 0.035, "{}", s);
+        //just below the sensitivity found, the second knee comes back
+        let more = kneedle_detailed(&test_data, &params.clone().sensitivity(s * 0.999)).unwrap();
+        assert_eq!(2, more.len());
+
+        //more knees than the curve has settle on all of them at S = 0
+        let (s, all) = kneedle_target_count(&test_data, 100, &params).unwrap();
+        assert_eq!(0.0, s);
+        assert_eq!(2, all.len());
+        assert!(more
+            .iter()
+            .zip(&all)
+            .all(|(more, all)| more.index == all.index));
+
+        let (s, none) = kneedle_target_count(&test_data, 0, &params).unwrap();
+        assert!(none.is_empty());
+        assert!(s > 1.0);
+    }
+
+    #[test]
+    fn roc_corner() {
+        //a classifier that reaches a true positive rate of 0.9 at a false positive rate of 0.1,
+        //and gains little after that, sampled at uneven thresholds
+        let mut roc: Vec<Point> = vec![
+            [0.0, 0.0],
+            [0.02, 0.3],
+            [0.05, 0.6],
+            [0.1, 0.9],
+            [0.15, 0.91],
+            [0.3, 0.93],
+            [0.55, 0.96],
+            [0.8, 0.98],
+            [1.0, 1.0],
+        ];
+        assert_eq!(Some([0.1, 0.9]), roc_knee(&roc).unwrap());
+
+        //the same curve listed from the highest threshold down, with a repeated rate
+        roc.reverse();
+        roc.insert(5, [0.15, 0.91]);
+        assert_eq!(Some([0.1, 0.9]), roc_knee(&roc).unwrap());
+
+        //a classifier no better than chance has no corner
+        let chance: Vec<Point> = (0..=10)
+            .map(|i| [i as f64 / 10.0, i as f64 / 10.0])
+            .collect();
+        assert_eq!(None, roc_knee(&chance).unwrap());
+    }
+
+    #[test]
+    fn no_candidates() {
+        //a falling line searched as concave increasing gives the strictly falling difference
+        //curve 1 - 2x, without any interior extremum
+        let test_data: Vec<Point> = (0..10).map(|i| [i as f64, 9.0 - i as f64]).collect();
+        let curve = difference_curve(&test_data, 0).unwrap();
+        assert!(curve.windows(2).all(|pair| pair[1][1] < pair[0][1]));
+
+        let mut candidates = vec![0];
+        find_candidate_indices(&curve, false, false, 0.0, &mut candidates);
+        assert!(candidates.is_empty());
+
+        let params = KneedleParams::new().smoothing_window(0);
+        assert!(kneedle_with(&test_data, &params).unwrap().is_empty());
+        assert!(!has_knee(&test_data, &params).unwrap());
+    }
+
+    #[test]
+    fn candidates_reported() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new()
+            .curve(Curve::ConvexDecreasing)
+            .smoothing_window(1);
+
+        let report = candidate_report(&test_data, &params).unwrap();
+        let indices: Vec<usize> = report.iter().map(|candidate| candidate.index).collect();
+        assert_eq!(vec![26, 32], indices);
+        let passed: Vec<bool> = report.iter().map(|candidate| candidate.passed).collect();
+        assert_eq!(vec![true, false], passed);
+        assert_eq!(26, kneedle_detailed(&test_data, &params).unwrap()[0].index);
+
+        //elbows are minima, the curve has to rise past a threshold above each of them
+        for candidate in &report {
+            assert!(candidate.threshold > candidate.diff_height);
+        }
+
+        //the rejected one passes once the sensitivity no longer asks for a rise
+        let report = candidate_report(&test_data, &params.clone().sensitivity(0.0)).unwrap();
+        assert!(report.iter().all(|candidate| candidate.passed));
+    }
+
+    #[test]
+    fn approx_eq_across_precisions() {
+        let test_data = bumpy_data();
+        let single: Vec<[f32; 2]> = test_data
+            .iter()
+            .map(|&[x, y]| [x as f32, y as f32])
+            .collect();
+        let params = KneedleParams::new()
+            .curve(Curve::ConvexDecreasing)
+            .smoothing_window(1);
+
+        let double = kneedle_detailed(&test_data, &params).unwrap();
+        let single = kneedle_detailed(&single, &params).unwrap();
+        assert_eq!(double.len(), single.len());
+        assert_ne!(double, single);
+        for (double, single) in double.iter().zip(&single) {
+            assert!(double.approx_eq(single, 1e-3));
+            assert!(!double.approx_eq(single, 0.0));
+        }
+
+        let mut moved = double[0].clone();
+        moved.index += 1;
+        assert!(!double[0].approx_eq(&moved, 1.0));
+    }
+
+    #[test]
+    fn sweep() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new().curve(Curve::ConvexDecreasing);
+
+        let sweep = kneedle_sweep(&test_data, &[0.5, 1.0, 2.0, 3.0], &params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(27, sweep.knee.index);
+        assert_approx_eq!(1.0, sweep.agreement);
+
+        //the shallow dip of fractional_sensitivity only passes for the eager sensitivity
+        let diffs = [
+            0.0, 0.1, 0.2, 0.3, 0.4, 0.25, 0.27, 0.5, 0.6, 0.7, 0.8, 0.7, 0.6, 0.5, 0.4, 0.3, 0.2,
+            0.1, 0.0, 0.0,
+        ];
+        let test_data: Vec<Point> = diffs
+            .iter()
+            .enumerate()
+            .map(|(i, diff)| [i as f64, i as f64 / 19.0 + diff])
+            .collect();
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let sweep = kneedle_sweep(&test_data, &[0.5, 2.0, 3.0], &params)
+            .unwrap()
+            .unwrap();
+        assert_approx_eq!(10.0, sweep.knee.x);
+        assert_approx_eq!(1.0, sweep.agreement);
+        assert_eq!(None, kneedle_sweep(&test_data, &[], &params).unwrap());
+    }
+
+    #[test]
+    fn auto_smooth() {
+        //a pseudo-random generator for reproducible noise of up to +-2 on a range of 100
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut noise = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 4.0
+        };
+        let curve = |x: f64| 100.0 * (1.0 - (-x / 100.0).exp());
+        let clean: Vec<Point> = (0..1000).map(|i| [i as f64, curve(i as f64)]).collect();
+        let noisy: Vec<Point> = clean.iter().map(|&[x, y]| [x, y + noise()]).collect();
+        let params = KneedleParams::new();
+
+        assert_eq!(1, auto_smoothing_window(&clean));
+        let window = auto_smoothing_window(&noisy);
+        assert!((10..=50).contains(&window));
+
+        //the default window finds a knee in almost every wiggle of the noise
+        assert!(kneedle_with(&noisy, &params).unwrap().len() > 100);
+
+        let expected = super::single_knee(&clean, &params.clone().smoothing_window(0))
+            .unwrap()
+            .unwrap();
+        let knees = kneedle_auto_smooth(&noisy, &params).unwrap();
+        assert!(knees.len() <= 3);
+        assert!(knees
+            .iter()
+            .any(|knee| (knee[0] - expected[0]).abs() < 50.0));
+    }
+
+    #[test]
+    fn linear() {
+        let line: Vec<Point> = (0..20).map(|x| [x as f64, x as f64]).collect();
+        let params = KneedleParams::new();
+
+        assert_eq!(Ok(false), has_knee(&line, &params));
+        assert!(kneedle_detailed(&line, &params).unwrap().is_empty());
+        assert_eq!(
+            Ok(true),
+            has_knee(&bumpy_data(), &params.curve(Curve::ConvexDecreasing))
+        );
+        assert_eq!(
+            Err(KneedleError::TooFewPoints(2)),
+            has_knee(&line[..2], &KneedleParams::new())
+        );
+    }
+
+    #[test]
+    fn max_difference_index() {
+        //the elbow of the mirrored bumpy curve is its lowest difference
+        let flipped = flip_x(&bumpy_data());
+        let curve = difference_curve(&flipped, 1).unwrap();
+        let negated: Vec<f64> = curve.iter().map(|point| -point[1]).collect();
+        let params = KneedleParams::new().smoothing_window(1).elbow(true);
+
+        let knees = kneedle_indices(&flipped, 1.0, 1, true).unwrap();
+        assert_eq!(vec![63], knees);
+        assert_eq!(Some(63), super::max_difference_index(&negated));
+        assert_eq!(
+            super::max_difference_index(&negated),
+            kneedle_ranked(&flipped, &params)
+                .unwrap()
+                .first()
+                .map(|knee| knee.index)
+        );
+
+        assert_eq!(None, super::max_difference_index::<f64>(&[]));
+        assert_eq!(None, super::max_difference_index(&[f64::NAN]));
+    }
+
+    #[test]
+    fn min_separation() {
+        //two bends a few samples apart, the second one sharper
+        let test_data: Vec<Point> = (0..30)
+            .map(|x| {
+                let y = match x {
+                    0..=4 => 10.0 * x as f64,
+                    5..=7 => 40.0 + 2.0 * (x - 4) as f64,
+                    8..=9 => 46.0 + 20.0 * (x - 7) as f64,
+                    _ => 86.0 + 0.5 * (x - 9) as f64,
+                };
+                [x as f64, y]
+            })
+            .collect();
+        let params = KneedleParams::new().smoothing_window(0);
+
+        let knees = kneedle_ranked(&test_data, &params).unwrap();
+        assert_eq!(
+            vec![9.0, 4.0],
+            knees.iter().map(|knee| knee.x).collect::<Vec<_>>()
+        );
+
+        //closer than the separation, so only the sharper bend is left
+        let params = params.min_separation(10.0);
+        let separated = kneedle_ranked(&test_data, &params).unwrap();
+        assert_eq!(vec![knees[0].clone()], separated);
+        assert_eq!(
+            vec![[9.0, 86.0]],
+            kneedle_with(&test_data, &params).unwrap()
+        );
+
+        //a separation of exactly the distance keeps both
+        let params = params.min_separation(5.0);
+        assert_eq!(knees, kneedle_ranked(&test_data, &params).unwrap());
+    }
+
+    #[test]
+    fn threshold_reset() {
+        let curve = |diffs: &[f64]| -> Vec<Vec<f64>> {
+            diffs
+                .iter()
+                .enumerate()
+                .map(|(i, &d)| vec![i as f64 / (diffs.len() - 1) as f64, d])
+                .collect()
+        };
+        let threshold_knees = |curve: Vec<Vec<f64>>, step: f64, find_elbow: bool| {
+            let mut knees = Vec::new();
+            super::threshold_knees(
+                &curve,
+                step,
+                find_elbow,
+                false,
+                0.0,
+                &mut Vec::new(),
+                &mut knees,
+            );
+            knees
+        };
+
+        //drops below the threshold of the local max at index 2
+        let knees = threshold_knees(curve(&[0.0, 0.3, 0.5, 0.45, 0.35, 0.2, 0.0]), -0.1, false);
+        assert_eq!(
+            vec![2],
+            knees.iter().map(|knee| knee.index).collect::<Vec<_>>()
+        );
+
+        //turns back up at a local min before reaching the threshold, so the later drop does not
+        //belong to the max at index 2 but to the flat max at index 6
+        let knees = threshold_knees(
+            curve(&[0.0, 0.3, 0.5, 0.45, 0.4, 0.4, 0.42, 0.42, 0.1, 0.0]),
+            -0.1,
+            false,
+        );
+        assert_eq!(
+            vec![6],
+            knees.iter().map(|knee| knee.index).collect::<Vec<_>>()
+        );
 
-use crate::maths::{gaussian_smooth2d, minmax_normalize};
+        //the same for elbows, mirrored
+        let knees = threshold_knees(
+            curve(&[0.0, -0.3, -0.5, -0.45, -0.4, -0.4, -0.42, -0.42, -0.1, 0.0]),
+            0.1,
+            true,
+        );
+        assert_eq!(
+            vec![6],
+            knees.iter().map(|knee| knee.index).collect::<Vec<_>>()
+        );
+    }
 
-fn find_candidate_indices(data: Vec<Vec<f64>>, find_minima: bool) -> Vec<usize> {
-    let rows = data.len();
-    let mut candidates: Vec<usize> = Vec::new();
-    for i in 1..(rows - 1) {
-        let prev = data[i - 1][1];
-        let cur = data[i][1];
-        let next = data[i + 1][1];
-        if (find_minima && prev > cur && next > cur) || (!find_minima && prev < cur && next < cur) {
-            candidates.push(i);
+    #[test]
+    fn threshold_tie() {
+        let curve = |diffs: &[f64]| -> Vec<Vec<f64>> {
+            diffs
+                .iter()
+                .enumerate()
+                .map(|(i, &d)| vec![i as f64 / (diffs.len() - 1) as f64, d])
+                .collect()
+        };
+        let knee_indices = |diffs: &[f64], step: f64, find_elbow: bool| {
+            let mut knees = Vec::new();
+            super::threshold_knees(
+                &curve(diffs),
+                step,
+                find_elbow,
+                false,
+                0.0,
+                &mut Vec::new(),
+                &mut knees,
+            );
+            knees.iter().map(|knee| knee.index).collect::<Vec<_>>()
+        };
+
+        //two bumps of the same height with a shallow dip between them tie, and the tie goes to
+        //the first, however deep the dip
+        for dip in [0.45, 0.41] {
+            let diffs = [0.0, 0.3, 0.5, dip, 0.5, 0.2, 0.0];
+            assert_eq!(vec![2], knee_indices(&diffs, -0.1, false));
+            let mirrored: Vec<f64> = diffs.iter().map(|d| -d).collect();
+            assert_eq!(vec![2], knee_indices(&mirrored, 0.1, true));
         }
+
+        //a higher second bump is no tie and is reported itself
+        assert_eq!(
+            vec![4],
+            knee_indices(&[0.0, 0.3, 0.5, 0.45, 0.55, 0.2, 0.0], -0.1, false)
+        );
+        //a dip past the threshold confirms both
+        assert_eq!(
+            vec![2, 4],
+            knee_indices(&[0.0, 0.3, 0.5, 0.3, 0.5, 0.2, 0.0], -0.1, false)
+        );
     }
-    candidates
-}
 
-#[allow(dead_code)]
-fn find_elbow_index(data: &[f64]) -> usize {
-    let mut best_score = f64::NAN;
-    let mut best_index = 0;
+    #[test]
+    fn sort_by_x() {
+        let test_data = [
+            [0.55, 0.9],
+            [0.1, 0.55],
+            [1.0, 1.0],
+            [0.35, 0.825],
+            [0.0, 0.0],
+            [0.875, 0.975],
+            [0.2, 0.75],
+            [0.675, 0.925],
+            [0.45, 0.875],
+            [0.775, 0.95],
+        ];
+        let params = KneedleParams::new().smoothing_window(1).sort_by_x(true);
+
+        let knees = kneedle_detailed(&test_data, &params).unwrap();
+        assert_eq!(1, knees.len());
+        assert_eq!(6, knees[0].index);
+        assert_approx_eq!(0.2, knees[0].x);
+        assert_approx_eq!(0.75, knees[0].y);
+
+        let mut with_nan = test_data;
+        with_nan[3][0] = f64::NAN;
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 3, col: 0 }),
+            kneedle_with(&with_nan, &params)
+        );
+    }
+
+    #[test]
+    fn extremum_tolerance() {
+        //a single bump followed by a flat stretch with floating-point wobble on it
+        let curve: Vec<Point> = (0..30)
+            .map(|i| {
+                let d = match i {
+                    0..=9 => 0.5 - (i as f64 - 5.0).abs() * 0.06,
+                    _ => 0.2 + if i % 2 == 0 { 1e-12 } else { -1e-12 },
+                };
+                [i as f64 / 29.0, d]
+            })
+            .collect();
 
-    for i in 0..data.len() {
-        if data[i].abs() > best_score {
-            best_score = data[i];
-            best_index = i;
+        let mut candidates = Vec::new();
+        let mut counts = Vec::new();
+        for tolerance in [0.0, 1e-13, 1e-9, 0.05] {
+            find_candidate_indices(&curve, false, false, tolerance, &mut candidates);
+            counts.push(candidates.len());
         }
+        assert_eq!(vec![10, 10, 1, 1], counts);
+        assert_eq!(vec![5], candidates);
+
+        //the bump stands out by 0.06 from its neighbours and no more
+        find_candidate_indices(&curve, false, false, 0.07, &mut candidates);
+        assert!(candidates.is_empty());
     }
-    best_index
-}
 
-fn prepare<I: AsRef<[f64]>>(
-    data: &[I],
-    smoothing_window: usize,
-) -> Result<Vec<Vec<f64>>, &'static str> {
-    //smooth the data to make local minimum/maximum easier to find (this is Step 1 in the paper)
-    let smoothed_data = gaussian_smooth2d(data, smoothing_window)?;
+    #[test]
+    fn sine_extrema() {
+        //two periods of a sine, a tenth of pi apart
+        let curve: Vec<Point> = (0..=40)
+            .map(|i| {
+                let x = i as f64 * core::f64::consts::PI / 10.0;
+                [x, x.sin()]
+            })
+            .collect();
 
-    //prepare the data into the unit range (step 2 of paper)
-    let mut normalized_data = minmax_normalize(smoothed_data)?;
+        assert_eq!(vec![5, 25], find_extrema(&curve, Extremum::Maxima));
+        assert_eq!(vec![15, 35], find_extrema(&curve, Extremum::Minima));
+        assert_eq!(vec![5, 15, 25, 35], find_extrema(&curve, Extremum::Both));
 
-    //subtract normalised x from normalised y (this is step 3 in the paper)
-    for i in 0..normalized_data.len() {
-        normalized_data[i][1] -= normalized_data[i][0]
+        //no interior points, no extrema
+        assert!(find_extrema(&curve[..2], Extremum::Both).is_empty());
+        assert!(find_extrema(&[], Extremum::Both).is_empty());
     }
 
-    Ok(normalized_data)
-}
+    #[test]
+    fn plateau() {
+        let test_data = [
+            [0.0, 0.0],
+            [1.0, 4.0],
+            [2.0, 8.0],
+            [3.0, 10.0],
+            [4.0, 10.0],
+            [5.0, 10.0],
+            [6.0, 7.0],
+            [7.0, 5.0],
+        ];
+        let mut candidates = Vec::new();
+        find_candidate_indices(&test_data, false, false, 0.0, &mut candidates);
+        assert_eq!(vec![3], candidates);
+
+        //a flat bottom for elbows, and a dip that is not flat next to it
+        let test_data = [
+            [0.0, 5.0],
+            [1.0, 2.0],
+            [2.0, 2.0],
+            [3.0, 4.0],
+            [4.0, 1.0],
+            [5.0, 3.0],
+        ];
+        find_candidate_indices(&test_data, true, false, 0.0, &mut candidates);
+        assert_eq!(vec![1, 4], candidates);
 
-fn compute_average_variance(data: Vec<Vec<f64>>) -> f64 {
-    let mut variance = 0.0;
+        //a flat run that rises again on one side is no extremum
+        let test_data = [[0.0, 0.0], [1.0, 2.0], [2.0, 2.0], [3.0, 3.0], [4.0, 1.0]];
+        find_candidate_indices(&test_data, false, false, 0.0, &mut candidates);
+        assert_eq!(vec![3], candidates);
 
-    for i in 0..data.len() - 1 {
-        variance += data[i + 1][0] - data[i][0];
+        //the knee at the start of a flat top of the difference curve is found
+        let curve: Vec<Point> = [0.0, 0.2, 0.5, 0.5, 0.5, 0.3, 0.1, 0.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| [i as f64 / 7.0, d])
+            .collect();
+        assert_eq!(
+            vec![curve[2]],
+            kneedle_prepared(&curve, 1.0, false).unwrap()
+        );
     }
-    variance / (data.len() - 1) as f64
-}
 
+    #[test]
+    fn weighted() {
+        //a saturating curve with a burst of noise around x = 20 that reads as a second knee
+        let mut test_data: Vec<Point> = (0..30)
+            .map(|i| [i as f64, 100.0 * (1.0 - (-i as f64 / 3.0).exp())])
+            .collect();
+        for (i, noise) in [(19, 4.0), (20, 12.0), (21, -3.0)] {
+            test_data[i][1] += noise;
+        }
+        let params = KneedleParams::new().smoothing_window(1);
 
-pub fn flip_x<I>(
-    data: &[I],
-    ) -> Vec<Vec<f64>> 
-where
-    I: AsRef<[f64]> + Clone,
-{
-    let mut xmax = f64::MIN;
+        let knees = kneedle_with(&test_data, &params).unwrap();
+        assert_eq!(
+            vec![7.0, 19.0],
+            knees.iter().map(|knee| knee[0]).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            knees,
+            kneedle_weighted(&test_data, &[1.0; 30], &params).unwrap()
+        );
 
-    for i in 0..data.len() {
-        xmax = xmax.max(data[i].as_ref()[0]);
+        let mut weights = [1.0; 30];
+        weights[18..22].fill(0.05);
+        assert_eq!(
+            vec![test_data[7]],
+            kneedle_weighted(&test_data, &weights, &params).unwrap()
+        );
+        //the weights follow the rows when they are searched in reverse
+        let reversed: Vec<Point> = test_data.iter().rev().copied().collect();
+        weights.reverse();
+        assert_eq!(
+            vec![test_data[7]],
+            kneedle_weighted(&reversed, &weights, &params).unwrap()
+        );
+
+        assert_eq!(
+            Err(KneedleError::WeightsMismatch {
+                data: 30,
+                weights: 29
+            }),
+            kneedle_weighted(&test_data, &weights[1..], &params)
+        );
+        weights[3] = 0.0;
+        assert_eq!(
+            Err(KneedleError::InvalidWeight { row: 3 }),
+            kneedle_weighted(&test_data, &weights, &params)
+        );
     }
 
-    let mut out = Vec::new();
+    #[test]
+    fn custom_candidates() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        let params = KneedleParams::new().smoothing_window(1);
 
-    for row in data.iter().rev() {
-        out.push(vec![xmax - row.as_ref()[0], row.as_ref()[1]]);
+        //the built-in local maxima give the same knees
+        let local_maxima = |curve: &[Point]| {
+            (1..curve.len() - 1)
+                .filter(|&i| curve[i][1] > curve[i - 1][1] && curve[i][1] > curve[i + 1][1])
+                .collect()
+        };
+        assert_eq!(
+            kneedle_with(&test_data, &params).unwrap(),
+            kneedle_with_candidates(&test_data, &params, local_maxima).unwrap()
+        );
+
+        //every interior point is tried, in any order. each candidate resets the threshold of the
+        //one before it, so only the last can be confirmed, but the search runs through
+        let mut calls = 0;
+        let all_interior = |curve: &[Point]| {
+            calls += 1;
+            assert_eq!(test_data.len(), curve.len());
+            (1..curve.len() - 1).rev().chain([0, 1, 99]).collect()
+        };
+        let knees = kneedle_with_candidates(&test_data, &params, all_interior).unwrap();
+        assert_eq!(1, calls);
+        assert!(knees.len() <= 1 && knees.iter().all(|knee| test_data.contains(knee)));
+
+        assert_eq!(
+            vec![[0.2, 0.75]],
+            kneedle_with_candidates(&test_data, &params, |_: &[Point]| vec![2]).unwrap()
+        );
+        assert_eq!(
+            Vec::<Point>::new(),
+            kneedle_with_candidates(&test_data, &params, |_: &[Point]| Vec::new()).unwrap()
+        );
     }
 
-    out
-}
+    #[test]
+    fn elbow_and_knee() {
+        //a logistic curve, bending up around x = 3 and leveling off around x = 7
+        let test_data: Vec<Point> = (0..=40)
+            .map(|i| {
+                let x = i as f64 / 4.0;
+                [x, 1.0 / (1.0 + (5.0 - x).exp())]
+            })
+            .collect();
 
-pub fn kneedle<I>(
-    data: &[I],
-    s: i32,
-    smoothing_window: usize,
-    find_elbow: bool,
-) -> Result<Vec<I>, &'static str>
-where
-    I: AsRef<[f64]> + Clone,
-{
-    if data.is_empty() {
-        return Err("Empty data");
+        let (elbows, knees) = detect_elbow_and_knee(&test_data, 1.0, 1).unwrap();
+        assert_eq!(1, elbows.len());
+        assert_eq!(1, knees.len());
+        assert!(elbows[0].x < 5.0 && knees[0].x > 5.0);
+
+        let params = KneedleParams::new().smoothing_window(1);
+        assert_eq!(
+            kneedle_detailed(&test_data, &params.clone().elbow(true)).unwrap(),
+            elbows
+        );
+        assert_eq!(kneedle_detailed(&test_data, &params).unwrap(), knees);
     }
 
-    let datasize = data.len();
+    #[test]
+    fn flip_round_trip() {
+        let test_data = bumpy_data();
+        let (flipped, flip) = flip_x_mapped(&test_data);
+        assert_eq!(flip_x(&test_data), flipped);
+        assert_eq!(flip.unflip(&flipped), test_data);
 
-    if data[0].as_ref().len() != 2 {
-        return Err("all data should be 2 dimensional");
+        //ascending x stays ascending, and every flipped row maps back onto its original
+        assert!(flipped.windows(2).all(|pair| pair[0][0] < pair[1][0]));
+        for (i, row) in flipped.iter().enumerate() {
+            assert_eq!(vec![flip.x(row[0]), row[1]], test_data[flip.index(i)]);
+        }
+
+        //a knee of the flipped curve is found at the same row of the original
+        let params = KneedleParams::new().smoothing_window(1);
+        let flipped_knees = kneedle_indices(&flipped, 1.0, 1, true).unwrap();
+        assert_eq!(
+            super::find_knees(&test_data, &params.curve(Curve::ConvexDecreasing))
+                .unwrap()
+                .iter()
+                .map(|knee| knee.index)
+                .collect::<Vec<_>>(),
+            flipped_knees
+                .iter()
+                .rev()
+                .map(|&i| flip.index(i))
+                .collect::<Vec<_>>()
+        );
     }
 
-    //do steps 1,2,3 of the paper in the prepare method
-    let normalized_data = prepare(data, smoothing_window)?;
+    #[test]
+    fn trace() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(Curve::ConvexDecreasing);
 
-    //find candidate indices (this is step 4 in the paper)
-    let candidate_indices = find_candidate_indices(normalized_data.clone(), find_elbow);
+        let trace = kneedle_trace(&test_data, &params).unwrap();
+        assert_eq!(kneedle_with(&test_data, &params).unwrap(), trace.knees);
+
+        //the stages line up row for row, flipped as they are searched
+        assert_eq!(flip_x(&test_data), trace.raw);
+        assert_eq!(test_data.len(), trace.smoothed.len());
+        for (normalized, difference) in trace.normalized.iter().zip(&trace.difference) {
+            assert_approx_eq!(normalized[0], difference[0]);
+            assert_approx_eq!(normalized[1] - normalized[0], difference[1]);
+        }
+        //the buffers the search ran on, the same as the stages taken one at a time
+        let smoothed = smooth(&flip_x(&test_data), &params).unwrap();
+        assert_eq!(to_f64_points(&smoothed), trace.smoothed);
+        let normalized = minmax_normalize(smoothed).unwrap();
+        assert_eq!(to_f64_points(&normalized), trace.normalized);
+        //the knee of the flipped curve is one of the candidates
+        let knee = test_data.len() - 1 - super::find_knees(&test_data, &params).unwrap()[0].index;
+        assert!(trace.candidates.contains(&knee));
+    }
 
-    //go through each candidate index, i, and see if the indices after i are satisfy the threshold requirement
-    //(this is step 5 in the paper)
+    #[test]
+    fn converted() {
+        let test_data: Vec<[i32; 2]> = bumpy_data()
+            .iter()
+            .map(|&[x, y]| [x as i32, y.round() as i32])
+            .collect();
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(Curve::ConvexDecreasing);
 
-    let mut step = compute_average_variance(normalized_data.clone());
+        let as_f64: Vec<Point> = test_data
+            .iter()
+            .map(|&[x, y]| [x as f64, y as f64])
+            .collect();
+        let expected: Vec<[i32; 2]> = kneedle_with(&as_f64, &params)
+            .unwrap()
+            .iter()
+            .map(|&[x, y]| [x as i32, y as i32])
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, kneedle_converted(&test_data, &params).unwrap());
 
-    if find_elbow {
-        step *= s as f64;
-    } else {
-        step *= -s as f64;
+        //the largest u32 values survive the conversion
+        let wide: Vec<[u32; 2]> = test_data
+            .iter()
+            .map(|&[x, y]| [u32::MAX - 100 + x as u32, y as u32])
+            .collect();
+        assert_eq!(
+            expected
+                .iter()
+                .map(|&[x, y]| [u32::MAX - 100 + x as u32, y as u32])
+                .collect::<Vec<_>>(),
+            kneedle_converted(&wide, &params).unwrap()
+        );
+    }
+
+    #[test]
+    fn zscore() {
+        //a saturating curve with one reading ten times too high near its end
+        let mut test_data: Vec<Point> = (0..100)
+            .map(|i| [i as f64, 100.0 * (1.0 - (-i as f64 / 10.0).exp())])
+            .collect();
+        test_data[90][1] = 1000.0;
+        let params = KneedleParams::new().smoothing_window(0);
+
+        //in the unit range the rest of the curve is squeezed flat under the outlier
+        let min_max = kneedle_with(&test_data, &params).unwrap();
+        assert!(min_max.iter().all(|knee| knee[0] > 50.0), "{:?}", min_max);
+
+        let z_score = kneedle_with(
+            &test_data,
+            &params.clone().normalization(Normalization::ZScore),
+        )
+        .unwrap();
+        assert!(
+            z_score.iter().any(|knee| (5.0..=20.0).contains(&knee[0])),
+            "{:?}",
+            z_score
+        );
+
+        let trace =
+            kneedle_trace(&test_data, &params.normalization(Normalization::ZScore)).unwrap();
+        let mean: f64 = trace.normalized.iter().map(|row| row[1]).sum::<f64>() / 100.0;
+        assert!(mean.abs() < 1e-9);
     }
 
-    let mut local_min_max_pts: Vec<I> = Vec::new();
+    #[test]
+    fn selection() {
+        let test_data = bumpy_data();
+        let params = KneedleParams::new()
+            .sensitivity(0.2)
+            .smoothing_window(0)
+            .curve(Curve::ConvexDecreasing);
+
+        //a low sensitivity on the unsmoothed data passes every bump, the strongest at x = 27
+        let all = kneedle_with(&test_data, &params).unwrap();
+        assert_eq!(7, all.len());
+        assert_eq!([32.0, 3504.3], *all.last().unwrap());
+        assert_eq!(
+            all,
+            kneedle_with(&test_data, &params.clone().selection(Selection::All)).unwrap()
+        );
+
+        let strongest = kneedle_ranked(&test_data, &params).unwrap()[0].clone();
+        assert_eq!(27.0, strongest.x);
+        assert_eq!(
+            vec![[strongest.x, strongest.y]],
+            kneedle_with(
+                &test_data,
+                &params.clone().selection(Selection::FirstMostProminent)
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            vec![*all.last().unwrap()],
+            kneedle_with(&test_data, &params.clone().selection(Selection::Last)).unwrap()
+        );
 
-    //check each candidate to see if it is a real elbow/knee
-    //(this is step 6 in the paper)
-    for i in 0..candidate_indices.len() {
-        let candidate_index = candidate_indices[i];
-        let mut end = datasize;
-        if i + 1 < candidate_indices.len() {
-            end = candidate_indices[i + 1];
+        //a curve without knees stays without
+        let line: Vec<Point> = (0..10).map(|i| [i as f64, i as f64]).collect();
+        for selection in [Selection::FirstMostProminent, Selection::Last] {
+            assert!(kneedle_with(&line, &params.clone().selection(selection))
+                .unwrap()
+                .is_empty());
         }
+    }
 
-        let threshold = normalized_data[candidate_index][1] + step;
+    #[test]
+    fn extremum_arms() {
+        //a valley, with its bottom off centre so the arms differ in length
+        let valley: Vec<Point> = (0..=40)
+            .map(|i| {
+                let x = i as f64 / 4.0;
+                [x, (x - 4.0) * (x - 4.0)]
+            })
+            .collect();
+        let params = KneedleParams::new().smoothing_window(1);
+        let (left, right) = split_at_extremum(&valley, &params).unwrap();
+        let (left, right) = (left.unwrap(), right.unwrap());
+        assert!(left[0] < 4.0 && right[0] > 4.0, "{:?} {:?}", left, right);
 
-        for j in (candidate_index + 1)..end {
-            if (find_elbow && normalized_data[j][1] > threshold)
-                || (!find_elbow && normalized_data[j][1] < threshold)
-            {
-                local_min_max_pts.push(data[candidate_index].clone());
-                break;
-            }
+        //each arm gives the knee the detection finds on it alone
+        let arm = kneedle_ranked(
+            &valley[..=16],
+            &params.clone().curve(Curve::ConvexDecreasing),
+        )
+        .unwrap();
+        assert_eq!([arm[0].x, arm[0].y], left);
+
+        //the same curve upside down is a hill with the knees in the same places
+        let hill: Vec<Point> = valley.iter().map(|&[x, y]| [x, -y]).collect();
+        assert_eq!(
+            (Some([left[0], -left[1]]), Some([right[0], -right[1]])),
+            split_at_extremum(&hill, &params).unwrap()
+        );
+
+        //a monotone curve has its extremum at an end, leaving one arm too short
+        let rising: Vec<Point> = (0..10).map(|i| [i as f64, (i * i) as f64]).collect();
+        assert_eq!(None, split_at_extremum(&rising, &params).unwrap().1);
+    }
+
+    #[test]
+    fn absolute_threshold() {
+        //a difference curve with a small bump at x = 0.2, a dip of 0.05 after it and the main knee
+        //at x = 0.5, as y = x + d over the unit square, sampled more or less densely
+        let corners = [[0.0, 0.0], [0.2, 0.25], [0.3, 0.2], [0.5, 0.4], [1.0, 0.0]];
+        let sample = |n: usize| -> Vec<Point> {
+            (0..n)
+                .map(|i| {
+                    let x = i as f64 / (n - 1) as f64;
+                    let k = corners
+                        .iter()
+                        .rposition(|corner| corner[0] <= x)
+                        .unwrap()
+                        .min(3);
+                    let ([x0, d0], [x1, d1]) = (corners[k], corners[k + 1]);
+                    [x, x + d0 + (d1 - d0) * (x - x0) / (x1 - x0)]
+                })
+                .collect()
+        };
+        let count =
+            |n: usize, params: &KneedleParams| kneedle_with(&sample(n), params).unwrap().len();
+        let params = KneedleParams::new().smoothing_window(0);
+
+        //the relative threshold of 1 / (n - 1) only lets the dip confirm the bump on dense data
+        assert_eq!(1, count(11, &params));
+        assert_eq!(2, count(101, &params));
+
+        //an absolute one decides the same whatever the spacing
+        for (drop, knees) in [(0.1, 1), (0.02, 2)] {
+            let params = params.clone().threshold(ThresholdMode::Absolute(drop));
+            assert_eq!(knees, count(11, &params));
+            assert_eq!(knees, count(101, &params));
+            assert_eq!(
+                knees,
+                count(101, &params.clone().sensitivity(100.0)),
+                "s is not used"
+            );
         }
     }
-    Ok(local_min_max_pts)
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn borrowed() {
+        let test_data: Vec<Vec<f64>> = bumpy_data().iter().map(|row| row.to_vec()).collect();
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(Curve::ConvexDecreasing);
+
+        let borrowed = kneedle_borrowed(&test_data, &params).unwrap();
+        let copied = kneedle_with(&test_data, &params).unwrap();
+        assert!(!copied.is_empty());
+        assert_eq!(
+            copied,
+            borrowed
+                .iter()
+                .map(|row| [row[0], row[1]])
+                .collect::<Vec<_>>()
+        );
+
+        //the rows themselves, not equal copies of them
+        for (row, knee) in borrowed
+            .iter()
+            .zip(super::find_knees(&test_data, &params).unwrap())
+        {
+            assert!(core::ptr::eq(*row, &test_data[knee.index]));
+        }
+    }
 
     #[test]
-    fn it_works() {
+    fn neighbors() {
         let test_data = [
             [0.0, 0.0],
             [0.1, 0.55],
@@ -159,12 +3380,440 @@ mod tests {
             [0.875, 0.975],
             [1.0, 1.0],
         ];
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let knees = kneedle_with_neighbors(&test_data, &params).unwrap();
+        assert_eq!(vec![([0.1, 0.55], [0.2, 0.75], [0.35, 0.825])], knees);
+
+        //a knee on the last row is its own following neighbour
+        let params = params.include_endpoints(true).elbow(true);
+        let elbows = kneedle_with_neighbors(&test_data, &params).unwrap();
+        let last = elbows.last().unwrap();
+        assert_eq!(([0.875, 0.975], [1.0, 1.0], [1.0, 1.0]), *last);
+    }
+
+    #[test]
+    fn log_y_scale() {
+        //a saturating exponential, whose linear difference curve peaks where it is already
+        //close to the asymptote
+        let test_data: Vec<Point> = (1..=100)
+            .map(|i| {
+                let x = i as f64 / 10.0;
+                [x, 1.0 - (-x).exp()]
+            })
+            .collect();
+        let params = KneedleParams::new().smoothing_window(1);
+        let linear = kneedle_with(&test_data, &params).unwrap();
+        assert_eq!(vec![test_data[23]], linear);
+
+        //on a log axis the bend is found closer to the time constant of 1
+        let log_params = params.clone().y_scale(YScale::Log10);
+        let log = kneedle_with(&test_data, &log_params).unwrap();
+        assert_eq!(vec![test_data[16]], log);
+
+        //refined knees come back in linear units
+        let refined = kneedle_interpolated(&test_data, &log_params).unwrap();
+        assert!(refined[0][1] > test_data[15][1] && refined[0][1] < test_data[17][1]);
+
+        let mut with_zero = test_data.clone();
+        with_zero[4][1] = 0.0;
+        assert_eq!(
+            Err(KneedleError::NonPositiveY { row: 4 }),
+            kneedle_with(&with_zero, &log_params)
+        );
+    }
+
+    #[test]
+    fn interleaved_f32() {
+        let points: [[f32; 2]; 10] = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        let samples: Vec<f32> = points.iter().flatten().copied().collect();
+
+        assert_eq!(
+            vec![(0.2, 0.75)],
+            kneedle_interleaved_f32(&samples, 1.0, 1, false).unwrap()
+        );
+        assert_eq!(
+            kneedle(&points, 1.0, 1, false)
+                .unwrap()
+                .iter()
+                .map(|&[t, v]| (t, v))
+                .collect::<Vec<_>>(),
+            kneedle_interleaved_f32(&samples, 1.0, 1, false).unwrap()
+        );
+        assert_eq!(
+            Err(KneedleError::NotTwoDimensional),
+            kneedle_interleaved_f32(&samples[1..], 1.0, 1, false)
+        );
+    }
+
+    #[test]
+    fn sorted_values() {
+        //Zipf-like frequencies, the term of rank r being seen 1000 / r times. the curve is
+        //furthest below its chord where its slope -1000 / r^2 matches that of the chord, -20, at
+        //r = sqrt(50), between the terms of rank 7 and 8
+        let frequencies: Vec<f64> = (1..=50).map(|rank| 1000.0 / rank as f64).collect();
+        assert_eq!(
+            Some((6, 1000.0 / 7.0)),
+            knee_of_sorted_values(&frequencies, 1.0, 0).unwrap()
+        );
+
+        //the same values sorted the other way have their elbow at the same value
+        let ascending: Vec<f64> = frequencies.iter().rev().copied().collect();
+        assert_eq!(
+            Some((43, 1000.0 / 7.0)),
+            knee_of_sorted_values(&ascending, 1.0, 0).unwrap()
+        );
+
+        assert_eq!(
+            Err(KneedleError::TooFewPoints(2)),
+            knee_of_sorted_values(&[2.0, 1.0], 1.0, 1)
+        );
+    }
+
+    #[test]
+    fn monotonic() {
+        //rises to its peak at x = pi / 2 and falls back to sin(3) > 0
+        let sine: Vec<Point> = (0..=30)
+            .map(|i| [i as f64 / 10.0, (i as f64 / 10.0).sin()])
+            .collect();
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let strict = params.clone().monotonic_tolerance(Some(0.0));
+        assert_eq!(
+            Err(KneedleError::NonMonotonic { index: 17 }),
+            kneedle_with(&sine, &strict)
+        );
+        //the step back from 1.6 to 1.7 is below 1% of the range of y
+        let tolerant = params.clone().monotonic_tolerance(Some(0.01));
+        assert_eq!(
+            Err(KneedleError::NonMonotonic { index: 18 }),
+            kneedle_with(&sine, &tolerant)
+        );
+        assert!(kneedle_with(&sine, &params).is_ok());
+
+        //the index refers to the rows as given when they are searched in reverse
+        let reversed: Vec<Point> = sine.iter().rev().copied().collect();
+        assert_eq!(
+            Err(KneedleError::NonMonotonic { index: 13 }),
+            kneedle_with(&reversed, &strict)
+        );
+
+        //the bumps of real data turn back by less than 1% of the range
+        let test_data = bumpy_data();
+        let params = KneedleParams::new().curve(Curve::ConvexDecreasing);
+        assert_eq!(
+            Err(KneedleError::NonMonotonic { index: 44 }),
+            kneedle_with(&test_data, &params.clone().monotonic_tolerance(Some(0.0)))
+        );
+        assert_eq!(
+            kneedle_with(&test_data, &params).unwrap(),
+            kneedle_with(&test_data, &params.clone().monotonic_tolerance(Some(0.01))).unwrap()
+        );
+    }
+
+    #[test]
+    fn include_endpoints() {
+        //a reading that settles after one sample and then climbs steadily, the difference curve
+        //is highest at the first point and falls from there
+        let test_data: Vec<Point> = [10.0, 0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &y)| [i as f64, y])
+            .collect();
+        let params = KneedleParams::new().smoothing_window(0);
+
+        assert!(kneedle_with(&test_data, &params).unwrap().is_empty());
+        let endpoints = params.clone().include_endpoints(true);
+        assert_eq!(
+            vec![[0.0, 10.0]],
+            kneedle_with(&test_data, &endpoints).unwrap()
+        );
+
+        //an elbow at the last point, where the climb drops back
+        let mut dropping: Vec<Point> = (0..8).map(|i| [i as f64, i as f64]).collect();
+        dropping.push([8.0, 5.0]);
+        let elbow = params.elbow(true);
+        assert!(kneedle_with(&dropping, &elbow).unwrap().is_empty());
+        assert_eq!(
+            vec![[8.0, 5.0]],
+            kneedle_with(&dropping, &elbow.include_endpoints(true)).unwrap()
+        );
+    }
+
+    #[test]
+    fn log_scale() {
+        //a learning-rate sweep from 1e-5 to 1, rising steadily in log x up to 1e-3 and then
+        //levelling off, which a linear axis squeezes into its first few percent
+        let ys = [0.0, 1.0, 2.0, 3.0, 4.0, 4.3, 4.5, 4.6, 4.7, 4.75, 4.8];
+        let test_data: Vec<Point> = ys
+            .iter()
+            .enumerate()
+            .map(|(i, &y)| [10f64.powf(i as f64 / 2.0 - 5.0), y])
+            .collect();
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let linear = kneedle_detailed(&test_data, &params).unwrap();
+        assert_eq!(
+            vec![6],
+            linear.iter().map(|knee| knee.index).collect::<Vec<_>>()
+        );
+
+        let log = params.clone().x_scale(XScale::Log10);
+        let knees = kneedle_detailed(&test_data, &log).unwrap();
+        assert_eq!(
+            vec![4],
+            knees.iter().map(|knee| knee.index).collect::<Vec<_>>()
+        );
+        assert_eq!(1e-3, knees[0].x);
+
+        let interpolated = kneedle_interpolated(&test_data, &log).unwrap();
+        assert!(test_data[3][0] < interpolated[0][0] && interpolated[0][0] < test_data[5][0]);
+
+        let mut with_zero = test_data.clone();
+        with_zero[3][0] = 0.0;
+        assert_eq!(
+            Err(KneedleError::NonPositiveX { row: 3 }),
+            kneedle_with(&with_zero, &log)
+        );
+        assert_eq!(
+            Err(KneedleError::NonPositiveX { row: 0 }),
+            kneedle_with(&[[-1.0, 0.0], [1.0, 1.0], [2.0, 1.5]], &log)
+        );
+    }
+
+    #[test]
+    fn duplicate_x() {
+        let test_data = [
+            [0.0, 0.0],
+            [1.0, 60.0],
+            [1.0, 70.0],
+            [2.0, 80.0],
+            [3.0, 85.0],
+            [4.0, 90.0],
+            [5.0, 95.0],
+            [6.0, 96.0],
+            [7.0, 97.0],
+            [8.0, 98.0],
+            [9.0, 99.0],
+        ];
+        let params = KneedleParams::new().smoothing_window(1);
+
+        assert_eq!(
+            Err(KneedleError::DuplicateX { row: 2 }),
+            kneedle_with(&test_data, &params)
+        );
+        assert_eq!(
+            Err(KneedleError::DuplicateX { row: 1 }),
+            kneedle_with(&[[1.0, 2.0], [1.0, 3.0], [2.0, 5.0]], &params)
+        );
+
+        let keep_first = params.clone().duplicate_x(DuplicateX::KeepFirst);
+        let knees = kneedle_detailed(&test_data, &keep_first).unwrap();
+        assert_eq!(1, knees.len());
+        assert_eq!(3, knees[0].index);
 
-        let knee_points = kneedle(&test_data, 1, 1, false).unwrap();
+        let average = params.duplicate_x(DuplicateX::Average);
+        let knees = kneedle_detailed(&test_data, &average).unwrap();
+        assert_eq!(1, knees.len());
+        assert_eq!(3, knees[0].index);
+        assert_eq!(
+            Err(KneedleError::TooFewPoints(2)),
+            kneedle_with(&[[1.0, 2.0], [1.0, 3.0], [2.0, 5.0]], &average)
+        );
+    }
+
+    #[test]
+    fn xy() {
+        let x = [0.0, 0.1, 0.2, 0.35, 0.45, 0.55, 0.675, 0.775, 0.875, 1.0];
+        let y = [0.0, 0.55, 0.75, 0.825, 0.875, 0.9, 0.925, 0.95, 0.975, 1.0];
+        let params = KneedleParams::new().smoothing_window(1);
 
+        let knee_points = kneedle_xy(&x, &y, &params).unwrap();
         assert_eq!(1, knee_points.len());
-        assert_approx_eq!(0.2, knee_points[0][0]);
-        assert_approx_eq!(0.75, knee_points[0][1]);
+        assert_approx_eq!(0.2, knee_points[0].0);
+        assert_approx_eq!(0.75, knee_points[0].1);
+
+        assert_eq!(
+            Err(KneedleError::LengthMismatch { x: 10, y: 9 }),
+            kneedle_xy(&x, &y[1..], &params)
+        );
+    }
+
+    #[test]
+    fn point_conversions() {
+        assert_eq!([1.0, 8.0], Point::from((1.0, 8.0)));
+        assert_eq!([1.0, 8.0], Point::from([1.0, 8.0]));
+        assert_eq!((1.0, 8.0), <(f64, f64)>::from([1.0, 8.0]));
+
+        let row = [1.0, 8.0, 3.0];
+        assert_eq!(Some([1.0, 8.0]), Point::try_from(&row[..2]).ok());
+        assert_eq!(
+            Err(KneedleError::NotTwoDimensional),
+            Point::try_from(&row[..]).map_err(KneedleError::from)
+        );
+
+        //rows of any length checked on the way in
+        let rows: Vec<Vec<f64>> = bumpy_data().iter().map(|row| row.to_vec()).collect();
+        let convert = |rows: &[Vec<f64>]| -> Result<Vec<Point>, KneedleError> {
+            Ok(rows
+                .iter()
+                .map(|row| Point::try_from(&row[..]))
+                .collect::<Result<_, _>>()?)
+        };
+        assert_eq!(bumpy_data().to_vec(), convert(&rows).unwrap());
+        let mut ragged = rows.clone();
+        ragged[3].push(0.0);
+        assert_eq!(Err(KneedleError::NotTwoDimensional), convert(&ragged));
+    }
+
+    #[test]
+    fn interleaved() {
+        let test_data = bumpy_data();
+        let flat: Vec<f64> = test_data.iter().flatten().copied().collect();
+        let params = KneedleParams::new().curve(Curve::ConvexDecreasing);
+
+        assert_eq!(
+            kneedle_with(&test_data, &params).unwrap(),
+            kneedle_interleaved(&flat, &params).unwrap()
+        );
+        assert_eq!(
+            Err(KneedleError::NotTwoDimensional),
+            kneedle_interleaved(&flat[1..], &params)
+        );
+    }
+
+    #[test]
+    fn flat() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        let xy: Vec<f64> = test_data.iter().flatten().copied().collect();
+
+        let knees = kneedle_flat(&xy, 1.0, 1, false);
+        assert_eq!(0, knees.len() % 2);
+        let expected: Vec<f64> = kneedle(&test_data, 1.0, 1, false)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(vec![0.2, 0.75], expected);
+        assert_eq!(expected, knees);
+
+        assert!(kneedle_flat(&xy[1..], 1.0, 1, false).is_empty());
+        assert!(kneedle_flat(&xy[..4], 1.0, 1, false).is_empty());
+    }
+
+    #[test]
+    fn tuples() {
+        let test_data = vec![
+            (0.0, 0.0),
+            (1.0, 60.0),
+            (2.0, 80.0),
+            (3.0, 85.0),
+            (4.0, 90.0),
+            (5.0, 95.0),
+            (6.0, 96.0),
+            (7.0, 97.0),
+            (8.0, 98.0),
+            (9.0, 99.0),
+        ];
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let knee_points = kneedle_tuples(&test_data, &params).unwrap();
+        assert_eq!(vec![(2.0, 80.0)], knee_points);
+    }
+
+    #[test]
+    fn too_few_points() {
+        let empty: [Point; 0] = [];
+        assert_eq!(Err(KneedleError::EmptyData), kneedle(&empty, 1.0, 1, false));
+        assert_eq!(
+            Err(KneedleError::TooFewPoints(1)),
+            kneedle(&[[0.0, 0.0]], 1.0, 1, false)
+        );
+        assert_eq!(
+            Err(KneedleError::TooFewPoints(2)),
+            kneedle(&[[0.0, 0.0], [1.0, 1.0]], 1.0, 1, true)
+        );
+        assert_eq!(
+            Err(KneedleError::TooFewPoints(2)),
+            kneedle_auto(&[[0.0, 0.0], [1.0, 1.0]], 1.0, 1)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let ragged = vec![vec![0.0, 0.0], vec![1.0], vec![2.0, 2.0]];
+        assert_eq!(
+            Err(KneedleError::RaggedRows {
+                row: 1,
+                expected: 2,
+                got: 1
+            }),
+            kneedle(&ragged, 1.0, 1, false)
+        );
+        //caught before the preprocessing copies the rows
+        let mut short: Vec<Vec<f64>> = (1..10).map(|i| vec![i as f64, i as f64]).collect();
+        short[5].pop();
+        assert_eq!(
+            Err(KneedleError::RaggedRows {
+                row: 5,
+                expected: 2,
+                got: 1
+            }),
+            kneedle_with(&short, &KneedleParams::new().x_scale(XScale::Log10))
+        );
+
+        let three_dimensional = [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [2.0, 2.0, 2.0]];
+        assert_eq!(
+            Err(KneedleError::NotTwoDimensional),
+            kneedle(&three_dimensional, 1.0, 1, false)
+        );
+
+        let mut nan_y = bumpy_data();
+        nan_y[4][1] = f64::NAN;
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 4, col: 1 }),
+            kneedle(&nan_y, 1.0, 1, false)
+        );
+        let mut infinite_x = bumpy_data();
+        infinite_x[7][0] = f64::INFINITY;
+        let params = KneedleParams::new().curve(Curve::ConvexDecreasing);
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 7, col: 0 }),
+            kneedle_with(&infinite_x, &params)
+        );
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 7, col: 0 }),
+            gaussian_smooth2d(&infinite_x, 1)
+        );
+        let skipped = kneedle_detailed(&infinite_x, &params.non_finite(NonFinite::Skip)).unwrap();
+        assert!(skipped
+            .iter()
+            .all(|knee| knee.index != 7 && knee.x.is_finite()));
+
+        let error: Box<dyn std::error::Error> = Box::new(KneedleError::TooFewPoints(2));
+        assert_eq!("need at least 3 points, got 2", error.to_string());
     }
 
     #[test]
@@ -182,27 +3831,27 @@ mod tests {
             [1.0,        4.09090909],
         ];*/
 
-    let mut test_data = vec![vec![0.0, -5.0]];
-
-    // Figure 2 depicts how Kneedle works for data points drawn
-    // from the curve y = −1/x + 5 where x-values are between 0
-    // and 1.
-    for i in 1..11 {
-        test_data.push(vec![i as f64 / 10.0, (-1.0/i as f64) + 5.0]);
-    }
+        let mut test_data = vec![vec![0.0, -5.0]];
 
+        // Figure 2 depicts how Kneedle works for data points drawn
+        // from the curve y = −1/x + 5 where x-values are between 0
+        // and 1.
+        for i in 1..11 {
+            test_data.push(vec![i as f64 / 10.0, (-1.0 / i as f64) + 5.0]);
+        }
 
-    println!("data {:?}", test_data);
+        println!("data {:?}", test_data);
 
-    let smoothed_data = gaussian_smooth2d(&test_data, 1).unwrap();
+        let smoothed_data = gaussian_smooth2d(&test_data, 1).unwrap();
 
         println!("smoothed {:?}", smoothed_data);
 
-            let normalized_data = prepare(&test_data, 1).unwrap();
+        let normalized_data =
+            prepare(&test_data, &KneedleParams::new().smoothing_window(1)).unwrap();
 
         println!("normalized {:?}", normalized_data);
 
-        let knee_points = kneedle(&test_data, 1, 1, false).unwrap();
+        let knee_points = kneedle(&test_data, 1.0, 1, false).unwrap();
         assert_eq!(1, knee_points.len());
         assert_approx_eq!(0.2, knee_points[0][0]);
         assert_approx_eq!(4.5, knee_points[0][1]);
@@ -223,7 +3872,7 @@ mod tests {
             [9.0, 100.0],
         ];
 
-        let knee_points = kneedle(&test_data, 1, 1, true).unwrap();
+        let knee_points = kneedle(&test_data, 1.0, 1, true).unwrap();
         assert_eq!(1, knee_points.len());
         assert_approx_eq!(7.0, knee_points[0][0]);
         assert_approx_eq!(20.0, knee_points[0][1]);
@@ -244,10 +3893,38 @@ mod tests {
             [9.0, 1.0],
         ];
 
-        let knee_points = kneedle(&flip_x(&test_data), 1, 1, true).unwrap();
+        let knee_points = kneedle(&flip_x(&test_data), 1.0, 1, true).unwrap();
         assert_eq!(1, knee_points.len());
         assert_approx_eq!(7.0, knee_points[0][0]);
         assert_approx_eq!(20.0, knee_points[0][1]);
+
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(Curve::ConvexDecreasing);
+        let knee_points = kneedle_with(&test_data, &params).unwrap();
+        assert_eq!(1, knee_points.len());
+        assert_approx_eq!(2.0, knee_points[0][0]);
+        assert_approx_eq!(20.0, knee_points[0][1]);
+
+        //the same points listed from the largest x down
+        let mut descending = test_data;
+        descending.reverse();
+        assert_eq!(
+            vec![[2.0, 20.0]],
+            kneedle_with(&descending, &params).unwrap()
+        );
+        assert_eq!(
+            vec![[2.0, 20.0]],
+            kneedle_with(&descending, &params.clone().x_order(XOrder::Descending)).unwrap()
+        );
+        assert_eq!(
+            vec![7],
+            kneedle_detailed(&descending, &params)
+                .unwrap()
+                .iter()
+                .map(|knee| knee.index)
+                .collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -265,10 +3942,25 @@ mod tests {
             [9.0, 0.0],
         ];
 
-        let knee_points = kneedle(&flip_x(&test_data), 1, 1, false).unwrap();
+        let knee_points = kneedle(&flip_x(&test_data), 1.0, 1, false).unwrap();
         assert_eq!(1, knee_points.len());
         assert_approx_eq!(2.0, knee_points[0][0]);
         assert_approx_eq!(80.0, knee_points[0][1]);
+
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(Curve::ConcaveDecreasing);
+        let knee_points = kneedle_with(&test_data, &params).unwrap();
+        assert_eq!(1, knee_points.len());
+        assert_approx_eq!(7.0, knee_points[0][0]);
+        assert_approx_eq!(80.0, knee_points[0][1]);
+
+        let mut descending = test_data;
+        descending.reverse();
+        assert_eq!(
+            vec![[7.0, 80.0]],
+            kneedle_with(&descending, &params).unwrap()
+        );
     }
 
     #[test]
@@ -286,15 +3978,61 @@ mod tests {
             [9.0, 99.0],
         ];
 
-        let knee_points = kneedle(&test_data, 1, 1, false).unwrap();
+        let knee_points = kneedle(&test_data, 1.0, 1, false).unwrap();
         assert_eq!(1, knee_points.len());
         assert_approx_eq!(2.0, knee_points[0][0]);
         assert_approx_eq!(80.0, knee_points[0][1]);
     }
 
     #[test]
-    fn bumpy() {
-        let test_data = [
+    fn detect_every_shape() {
+        let x = |i: usize| i as f64;
+        let shape = |ys: [f64; 10]| -> Vec<Point> { (0..10).map(|i| [x(i), ys[i]]).collect() };
+        let shapes = [
+            (
+                Curve::ConcaveIncreasing,
+                shape([0.0, 60.0, 80.0, 85.0, 90.0, 95.0, 96.0, 97.0, 98.0, 99.0]),
+                [2.0, 80.0],
+            ),
+            (
+                Curve::ConvexIncreasing,
+                shape([1.0, 2.0, 3.0, 4.0, 5.0, 10.0, 15.0, 20.0, 40.0, 100.0]),
+                [7.0, 20.0],
+            ),
+            (
+                Curve::ConcaveDecreasing,
+                shape([99.0, 98.0, 97.0, 96.0, 95.0, 90.0, 85.0, 80.0, 60.0, 0.0]),
+                [7.0, 80.0],
+            ),
+            (
+                Curve::ConvexDecreasing,
+                shape([100.0, 40.0, 20.0, 15.0, 10.0, 5.0, 4.0, 3.0, 2.0, 1.0]),
+                [2.0, 20.0],
+            ),
+        ];
+
+        let params = KneedleParams::new().smoothing_window(1);
+        for (expected, data, knee) in shapes {
+            let (curve, knees) = detect(&data, &params).unwrap();
+            assert_eq!(expected, curve);
+            assert_eq!(
+                vec![knee],
+                knees
+                    .iter()
+                    .map(|knee| [knee.x, knee.y])
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        //a straight line has no knee in any orientation
+        let line = shape(core::array::from_fn(|i| 2.0 * x(i)));
+        let (curve, knees) = detect(&line, &params).unwrap();
+        assert_eq!(params.curve, curve);
+        assert!(knees.is_empty());
+    }
+
+    pub(crate) fn bumpy_data() -> Vec<Point> {
+        vec![
             [0.0, 7305.0],
             [1.0, 6979.0],
             [2.0, 6666.6],
@@ -385,11 +4123,43 @@ mod tests {
             [90.0, 2063.7],
             [91.0, 2048.1],
             [92.0, 2031.9],
-            ];
-        let knee_points = kneedle(&flip_x(&test_data), 1, 1, true).unwrap();
+        ]
+    }
+
+    #[test]
+    fn bumpy() {
+        let test_data = bumpy_data();
+        let knee_points = kneedle(&flip_x(&test_data), 1.0, 1, true).unwrap();
         assert_eq!(1, knee_points.len());
-        //assert_approx_eq!(7.0, knee_points[0][0]);
-        assert_approx_eq!(15.0, knee_points[0][1]);
+        //the flipped x of the original x = 27 sample
+        assert_approx_eq!(65.0, knee_points[0][0]);
+        assert_approx_eq!(3745.3, knee_points[0][1]);
     }
 
+    #[test]
+    fn fractional_sensitivity() {
+        //the smoothed bumpy curve has a single candidate, which passes at either sensitivity
+        let test_data = flip_x(&bumpy_data());
+        assert_eq!(
+            kneedle(&test_data, 0.5, 1, true).unwrap(),
+            kneedle(&test_data, 2.0, 1, true).unwrap()
+        );
+
+        //a shallow dip before the main knee only counts as a knee for an eager sensitivity
+        let diffs = [
+            0.0, 0.1, 0.2, 0.3, 0.4, 0.25, 0.27, 0.5, 0.6, 0.7, 0.8, 0.7, 0.6, 0.5, 0.4, 0.3, 0.2,
+            0.1, 0.0, 0.0,
+        ];
+        let test_data: Vec<Point> = diffs
+            .iter()
+            .enumerate()
+            .map(|(i, diff)| [i as f64, i as f64 / 19.0 + diff])
+            .collect();
+
+        let eager = kneedle(&test_data, 0.5, 1, false).unwrap();
+        let conservative = kneedle(&test_data, 2.0, 1, false).unwrap();
+        assert_eq!(2, eager.len());
+        assert_eq!(1, conservative.len());
+        assert_approx_eq!(10.0, conservative[0][0]);
+    }
 }
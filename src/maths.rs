@@ -1,100 +1,1074 @@
-fn gaussian(x: f64, height: f64, center: f64, width: f64) -> f64 {
-    height * (-(x - center) * (x - center) / (2.0 * width * width)).exp()
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::error::KneedleError;
+use crate::float::Float;
+use crate::params::Boundary;
+
+/// The Gaussian function `height * exp(-(x - center)^2 / (2 * width^2))` behind both smoothing
+/// kernels, with `width` the standard deviation.
+///
+/// `gaussian_smooth2d` weighs a neighbour `d` samples away by `gaussian_kernel(d / w, 1, 0, 1)`,
+/// `gaussian_smooth_by_x` a row at distance `dx` by `gaussian_kernel(dx, 1, 0, bandwidth)`.
+///
+/// ```
+/// assert_eq!(2.0, kneedle::gaussian_kernel(3.0, 2.0, 3.0, 0.5));
+/// ```
+pub fn gaussian_kernel<T: Float>(x: T, height: T, center: T, width: T) -> T {
+    height * (-(x - center) * (x - center) / (T::from_f64(2.0) * width * width)).exp()
+}
+
+//weight of sample j when smoothing sample i, the offset has to be scaled in floating point or
+//every neighbour inside the window ends up with the same weight
+fn index_weight<T: Float>(i: usize, j: usize, w: usize) -> T {
+    let index_score = T::from_f64((j as f64 - i as f64) / w as f64).abs();
+    gaussian_kernel(index_score, T::ONE, T::ZERO, T::ONE)
 }
 
-pub fn gaussian_smooth2d<I: AsRef<[f64]>>(
+/// Smooths every dimension of `data` with a Gaussian kernel over `w` samples either side of each
+/// row (step 1 of the paper).
+///
+/// Rows can have any number of dimensions but all must match the first. Returns an error for
+/// empty data, zero-dimensional rows, rows of differing lengths or NaN and infinite values.
+///
+/// A window of 0 returns the data unchanged, 1 is the smallest window that smooths anything.
+///
+/// ```
+/// let smoothed = kneedle::gaussian_smooth2d(&[[0.0, 0.0], [1.0, 3.0], [2.0, 0.0]], 1).unwrap();
+/// assert!(smoothed[1][1] < 3.0);
+/// ```
+pub fn gaussian_smooth2d<T: Float, I: AsRef<[T]>>(
     data: &[I],
     w: usize,
-) -> Result<Vec<Vec<f64>>, &'static str> {
-    let datasize = data.len();
-    if datasize == 0 {
-        return Err("Empty data");
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    gaussian_smooth(data, w, None, Boundary::Truncate)
+}
+
+/// Same as `gaussian_smooth2d`, with the ends of the data treated as `boundary` says.
+///
+/// With `Boundary::Reflect` the window past an end is filled with the rows reflected through the
+/// end row, so the ends of a straight line stay where they are rather than being pulled towards
+/// the interior. Only one reflection is taken, a window wider than the data is still cut off.
+///
+/// ```
+/// use kneedle::Boundary;
+///
+/// let ramp = [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [3.0, 3.0f64]];
+/// let truncated = kneedle::gaussian_smooth2d_with_boundary(&ramp, 2, Boundary::Truncate).unwrap();
+/// let reflected = kneedle::gaussian_smooth2d_with_boundary(&ramp, 2, Boundary::Reflect).unwrap();
+/// assert!(truncated[0][1] > 0.5);
+/// assert!(reflected[0][1].abs() < 1e-12);
+/// ```
+pub fn gaussian_smooth2d_with_boundary<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    w: usize,
+    boundary: Boundary,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    gaussian_smooth(data, w, None, boundary)
+}
+
+//the Gaussian smoothing of every dimension behind the public variants
+pub(crate) fn gaussian_smooth<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    w: usize,
+    bandwidth: Option<f64>,
+    boundary: Boundary,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    let dimensions = check_rows(data)?;
+    let weights = kernel_weights(w, bandwidth);
+
+    let mut smoothed: Vec<Vec<T>> = vec![vec![T::ZERO; dimensions]; data.len()];
+    smooth_rows(data, &weights, None, boundary, &mut smoothed);
+
+    Ok(smoothed)
+}
+
+//rows from which smooth_rows splits the work across threads, below it spawning them costs more
+//than the smoothing they would share
+#[cfg(feature = "parallel")]
+pub(crate) const PARALLEL_ROWS: usize = 1 << 16;
+
+//smooths every row of data into the same row of smoothed, across the available threads with the
+//parallel feature once there are PARALLEL_ROWS of them, one row after another otherwise
+pub(crate) fn smooth_rows<T: Float, I: AsRef<[T]>, R: AsMut<[T]>>(
+    data: &[I],
+    weights: &[T],
+    point_weights: Option<&[T]>,
+    boundary: Boundary,
+    smoothed: &mut [R],
+) {
+    #[cfg(feature = "parallel")]
+    if data.len() >= PARALLEL_ROWS {
+        //slices of the rows can be shared with the threads whatever type the rows are
+        let rows: Vec<&[T]> = data.iter().map(|row| row.as_ref()).collect();
+        let mut smoothed: Vec<&mut [T]> = smoothed.iter_mut().map(|row| row.as_mut()).collect();
+        smooth_rows_threaded(&rows, weights, point_weights, boundary, &mut smoothed);
+        return;
     }
 
-    let dimensions = data[0].as_ref().len();
+    for (i, smoothed_row) in smoothed.iter_mut().enumerate() {
+        smooth_row(
+            data,
+            weights,
+            point_weights,
+            boundary,
+            i,
+            smoothed_row.as_mut(),
+        );
+    }
+}
 
-    if dimensions == 0 {
-        return Err("dimension cannot be 0");
+//smooth_rows with the rows split into one chunk per available thread
+#[cfg(feature = "parallel")]
+fn smooth_rows_threaded<T: Float>(
+    data: &[&[T]],
+    weights: &[T],
+    point_weights: Option<&[T]>,
+    boundary: Boundary,
+    smoothed: &mut [&mut [T]],
+) {
+    if smoothed.is_empty() {
+        return;
     }
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = smoothed.len().div_ceil(threads);
 
-    let mut smoothed: Vec<Vec<f64>> = vec![vec![0.0; dimensions]; datasize];
+    std::thread::scope(|scope| {
+        for (chunk_index, chunk) in smoothed.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(move || {
+                for (offset, smoothed_row) in chunk.iter_mut().enumerate() {
+                    let i = chunk_index * chunk_size + offset;
+                    smooth_row(data, weights, point_weights, boundary, i, smoothed_row);
+                }
+            });
+        }
+    });
+}
 
-    for i in 0..datasize {
-        if data[i].as_ref().len() != dimensions {
-            return Err("all rows must have the same dimension");
+/// Same as `gaussian_smooth2d`, with the standard deviation of the kernel set to `bandwidth`
+/// samples instead of `w`, which then only cuts the kernel off `w` samples either side.
+///
+/// A wide window with a narrow bandwidth keeps the shape of the kernel without truncating its
+/// tails, a narrow window with a wide bandwidth approaches a moving average. A bandwidth that is
+/// not positive returns the data unchanged, and `gaussian_smooth2d` is the same as a bandwidth of
+/// `w`.
+///
+/// ```
+/// let data = [[0.0, 0.0], [1.0, 0.0], [2.0, 3.0], [3.0, 0.0], [4.0, 0.0]];
+/// let narrow = kneedle::gaussian_smooth2d_with_bandwidth(&data, 2, 0.5).unwrap();
+/// let wide = kneedle::gaussian_smooth2d_with_bandwidth(&data, 2, 4.0).unwrap();
+/// assert!(wide[2][1] < narrow[2][1]);
+/// ```
+pub fn gaussian_smooth2d_with_bandwidth<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    w: usize,
+    bandwidth: f64,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    gaussian_smooth(data, w, Some(bandwidth), Boundary::Truncate)
+}
+
+/// Same as `gaussian_smooth2d`, with the rows split across the available threads however few
+/// there are.
+///
+/// With the `parallel` feature `gaussian_smooth2d` and the smoothing of the knee search already
+/// split curves of many rows across threads, this forces it for shorter ones too. Every row is
+/// computed exactly as the serial version does, so the output is identical.
+#[cfg(feature = "parallel")]
+pub fn gaussian_smooth2d_parallel<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    w: usize,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    let dimensions = check_rows(data)?;
+    let weights = kernel_weights(w, None);
+
+    let mut smoothed: Vec<Vec<T>> = vec![vec![T::ZERO; dimensions]; data.len()];
+    let rows: Vec<&[T]> = data.iter().map(|row| row.as_ref()).collect();
+    let mut rows_smoothed: Vec<&mut [T]> = smoothed.iter_mut().map(|row| &mut row[..]).collect();
+    smooth_rows_threaded(
+        &rows,
+        &weights,
+        None,
+        Boundary::Truncate,
+        &mut rows_smoothed,
+    );
+
+    Ok(smoothed)
+}
+
+/// Same as `gaussian_smooth2d`, but leaves the first dimension (x) untouched and only smooths the
+/// others.
+///
+/// Smoothing x blurs each x-value toward its neighbours, which distorts the axis when the samples
+/// are not evenly spaced.
+///
+/// ```
+/// let data = [[0.0, 0.0], [1.0, 3.0], [5.0, 0.0]];
+/// let smoothed = kneedle::gaussian_smooth_y(&data, 1).unwrap();
+/// assert_eq!(1.0, smoothed[1][0]);
+/// assert!(smoothed[1][1] < 3.0);
+/// ```
+pub fn gaussian_smooth_y<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    w: usize,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    let mut smoothed = gaussian_smooth2d(data, w)?;
+
+    for (smoothed_row, row) in smoothed.iter_mut().zip(data) {
+        smoothed_row[0] = row.as_ref()[0];
+    }
+
+    Ok(smoothed)
+}
+
+/// Smooths every dimension but the first (x) with a Gaussian kernel over the x-distance between
+/// rows, `bandwidth` being its standard deviation in the units of x.
+///
+/// Unlike `gaussian_smooth2d`, which weighs neighbours by how many samples away they are, rows
+/// far apart in x barely influence each other even when they are adjacent. Rows must be sorted by
+/// x, neighbours more than 3 bandwidths away are ignored. A bandwidth that is not positive returns
+/// the data unchanged.
+///
+/// ```
+/// let data = [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [10.0, 10.0]];
+/// let smoothed = kneedle::gaussian_smooth_by_x(&data, 1.0).unwrap();
+/// assert!(smoothed[2][1] < 1e-6);
+/// ```
+pub fn gaussian_smooth_by_x<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    bandwidth: f64,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    check_rows(data)?;
+
+    let mut smoothed: Vec<Vec<T>> = data.iter().map(|row| row.as_ref().to_vec()).collect();
+    if bandwidth.is_nan() || bandwidth <= 0.0 {
+        return Ok(smoothed);
+    }
+
+    let bandwidth = T::from_f64(bandwidth);
+    let cutoff = T::from_f64(3.0) * bandwidth;
+
+    for (i, smoothed_row) in smoothed.iter_mut().enumerate() {
+        let x = data[i].as_ref()[0];
+        let near = |row: &I| (row.as_ref()[0] - x).abs() <= cutoff;
+        let start = i - data[..i].iter().rev().take_while(|row| near(row)).count();
+        let end = i + data[i + 1..].iter().take_while(|row| near(row)).count();
+
+        let mut sum_weights: Vec<T> = vec![T::ZERO; smoothed_row.len()];
+        let mut sum_distance_weight = T::ZERO;
+
+        for row in &data[start..=end] {
+            let weight = gaussian_kernel(row.as_ref()[0], T::ONE, x, bandwidth);
+
+            for (sum, &value) in sum_weights.iter_mut().zip(row.as_ref()).skip(1) {
+                *sum += weight * value;
+            }
+            sum_distance_weight += weight;
         }
 
-        let mut start = 0;
-        let mut end = i + w;
+        for (smoothed, sum) in smoothed_row.iter_mut().zip(sum_weights).skip(1) {
+            *smoothed = sum / sum_distance_weight;
+        }
+    }
+
+    Ok(smoothed)
+}
+
+/// Replaces every value but the first (x) of each row with the median of the `window` rows
+/// centred on it, which removes isolated outliers that a Gaussian kernel would only spread out.
+///
+/// `window` must be odd, a width of 3 removes any single-sample spike. Near the ends the window
+/// shrinks symmetrically, so the first and last rows are kept as they are.
+///
+/// ```
+/// let data = [[0.0, 1.0], [1.0, 2.0], [2.0, 50.0], [3.0, 4.0], [4.0, 5.0]];
+/// let smoothed = kneedle::median_smooth_y(&data, 3).unwrap();
+/// assert_eq!(4.0, smoothed[2][1]);
+/// ```
+pub fn median_smooth_y<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    window: usize,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    check_rows(data)?;
+    if window.is_multiple_of(2) {
+        return Err(KneedleError::EvenWindow(window));
+    }
+
+    let half = window / 2;
+    let mut smoothed: Vec<Vec<T>> = data.iter().map(|row| row.as_ref().to_vec()).collect();
+    let mut values: Vec<T> = Vec::with_capacity(window);
 
-        if 0 < i as i32 - w as i32 {
-            start = i - w;
+    for (i, smoothed_row) in smoothed.iter_mut().enumerate() {
+        let half = half.min(i).min(data.len() - 1 - i);
+
+        for (d, smoothed) in smoothed_row.iter_mut().enumerate().skip(1) {
+            values.clear();
+            values.extend(data[i - half..=i + half].iter().map(|row| row.as_ref()[d]));
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            *smoothed = values[half];
         }
+    }
+
+    Ok(smoothed)
+}
+
+/// Replaces every value but the first (x) of each row with its exponential moving average,
+/// `alpha` times the value plus `1 - alpha` times the average up to the row before.
+///
+/// Each average only looks back, so it can be updated one row at a time, but it lags behind the
+/// data: a change shows up spread over the following rows, about `(1 - alpha) / alpha` rows late
+/// on average, and a knee is found later than it is. Smaller `alpha` smooths more and lags more.
+/// An `alpha` of 1 or more, or not positive, leaves the data as it is.
+///
+/// ```
+/// let data = [[0.0, 0.0], [1.0, 4.0], [2.0, 4.0]];
+/// let smoothed = kneedle::ema_smooth_y(&data, 0.5).unwrap();
+/// assert_eq!(vec![vec![0.0, 0.0], vec![1.0, 2.0], vec![2.0, 3.0]], smoothed);
+/// ```
+pub fn ema_smooth_y<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    alpha: f64,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    check_rows(data)?;
+
+    let mut smoothed: Vec<Vec<T>> = data.iter().map(|row| row.as_ref().to_vec()).collect();
+    if alpha.is_nan() || alpha <= 0.0 || alpha >= 1.0 {
+        return Ok(smoothed);
+    }
 
-        if datasize - 1 < i + w {
-            end = datasize - 1;
+    let alpha = T::from_f64(alpha);
+    for i in 1..smoothed.len() {
+        for d in 1..smoothed[i].len() {
+            smoothed[i][d] = ema_step(smoothed[i - 1][d], smoothed[i][d], alpha);
         }
+    }
 
-        let mut sum_weights: Vec<f64> = vec![0.0; dimensions];
-        let mut sum_index_weight = 0.0;
+    Ok(smoothed)
+}
 
-        for j in start..end + 1 {
-            let index_score = (((j as f64 - i as f64) / w as f64) as f64).abs();
-            let index_weight = gaussian(index_score, 1.0, 0.0, 1.0);
+//the exponential moving average after average, moved towards value by alpha
+pub(crate) fn ema_step<T: Float>(average: T, value: T, alpha: T) -> T {
+    average + alpha * (value - average)
+}
 
-            for n in 0..dimensions {
-                sum_weights[n] += index_weight * data[j].as_ref()[n];
+/// Smooths every value but the first (x) of each row with a Savitzky–Golay filter, the value at
+/// the centre of a least-squares polynomial of degree `order` fitted to the `window` rows centred
+/// on it.
+///
+/// The fit follows the shape of the curve, so a bend is kept much sharper than by a Gaussian
+/// kernel of similar width. Rows are assumed to be evenly spaced in x. `window` must be odd and
+/// larger than `order`. Near the ends the window shrinks symmetrically, down to the first and last
+/// rows which are kept as they are.
+///
+/// ```
+/// //a polynomial of degree at most `order` passes through unchanged
+/// let data: Vec<[f64; 2]> = (0..9).map(|i| [i as f64, (i * i) as f64]).collect();
+/// let smoothed = kneedle::savitzky_golay_smooth_y(&data, 5, 2).unwrap();
+/// assert!((smoothed[4][1] - 16.0).abs() < 1e-9);
+/// ```
+pub fn savitzky_golay_smooth_y<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    window: usize,
+    order: usize,
+) -> Result<Vec<Vec<T>>, KneedleError> {
+    check_rows(data)?;
+    if window.is_multiple_of(2) {
+        return Err(KneedleError::EvenWindow(window));
+    }
+    if order >= window {
+        return Err(KneedleError::OrderTooHigh { order, window });
+    }
+
+    //coefficients[h] filters a window of h rows either side, with the order capped to fit it
+    let half = window / 2;
+    let coefficients: Vec<Vec<T>> = (0..=half)
+        .map(|h| savitzky_golay_coefficients(h, order.min(2 * h)))
+        .collect();
+
+    let mut smoothed: Vec<Vec<T>> = data.iter().map(|row| row.as_ref().to_vec()).collect();
+
+    for (i, smoothed_row) in smoothed.iter_mut().enumerate() {
+        let h = half.min(i).min(data.len() - 1 - i);
+        let neighbours = &data[i - h..=i + h];
+
+        for (d, smoothed) in smoothed_row.iter_mut().enumerate().skip(1) {
+            let mut sum = T::ZERO;
+            for (&c, row) in coefficients[h].iter().zip(neighbours) {
+                sum += c * row.as_ref()[d];
             }
-            sum_index_weight += index_weight;
+            *smoothed = sum;
         }
+    }
 
-        for n in 0..dimensions {
-            smoothed[i][n] = sum_weights[n] / sum_index_weight;
+    Ok(smoothed)
+}
+
+//weights of the rows at offsets -half..=half giving the value at 0 of the least-squares
+//polynomial of degree order through them, from the normal equations G u = e0 with
+//G[a][b] = sum(z^(a + b)) and weight(z) = sum(u[a] * z^a)
+fn savitzky_golay_coefficients<T: Float>(half: usize, order: usize) -> Vec<T> {
+    let offsets: Vec<f64> = (0..=2 * half).map(|j| j as f64 - half as f64).collect();
+    let size = order + 1;
+
+    //power_sums[k] = sum(z^k)
+    let mut power_sums = vec![0.0; 2 * order + 1];
+    for &z in &offsets {
+        let mut power = 1.0;
+        for sum in power_sums.iter_mut() {
+            *sum += power;
+            power *= z;
         }
     }
 
-    Ok(smoothed)
+    let mut g: Vec<Vec<f64>> = vec![vec![0.0; size + 1]; size];
+    for (a, g_row) in g.iter_mut().enumerate() {
+        g_row[..size].copy_from_slice(&power_sums[a..a + size]);
+        g_row[size] = if a == 0 { 1.0 } else { 0.0 };
+    }
+
+    //Gauss-Jordan elimination with partial pivoting on the augmented matrix
+    for column in 0..size {
+        let pivot = (column..size)
+            .max_by(|&a, &b| g[a][column].abs().total_cmp(&g[b][column].abs()))
+            .expect("the column range is not empty");
+        g.swap(column, pivot);
+
+        let pivot_row = g[column].clone();
+        for (r, g_row) in g.iter_mut().enumerate() {
+            if r != column {
+                let factor = g_row[column] / pivot_row[column];
+                for (entry, &p) in g_row.iter_mut().zip(&pivot_row) {
+                    *entry -= factor * p;
+                }
+            }
+        }
+    }
+    let u: Vec<f64> = g
+        .iter()
+        .enumerate()
+        .map(|(a, row)| row[size] / row[a])
+        .collect();
+
+    offsets
+        .iter()
+        .map(|&z| T::from_f64(u.iter().rev().fold(0.0, |acc, &u_a| acc * z + u_a)))
+        .collect()
 }
 
-pub fn minmax_normalize(data: Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, &'static str> {
-    let datasize = data.len();
-    if datasize == 0 {
-        return Err("Empty data");
+//validate the shape and values of the input once up front, returning the dimension shared by every
+//row
+pub(crate) fn check_rows<T: Float, I: AsRef<[T]>>(data: &[I]) -> Result<usize, KneedleError> {
+    if data.is_empty() {
+        return Err(KneedleError::EmptyData);
     }
 
-    let dimensions = data[0].len();
+    let dimensions = data[0].as_ref().len();
 
     if dimensions == 0 {
-        return Err("dimension cannot be 0");
+        return Err(KneedleError::ZeroDimension);
     }
 
-    let mut min_each_dimension: Vec<f64> = vec![f64::MAX; dimensions];
-    let mut max_each_dimension: Vec<f64> = vec![f64::MIN_POSITIVE; dimensions];
+    for (i, row) in data.iter().enumerate() {
+        if row.as_ref().len() != dimensions {
+            return Err(KneedleError::RaggedRows {
+                row: i,
+                expected: dimensions,
+                got: row.as_ref().len(),
+            });
+        }
+        if let Some(col) = row.as_ref().iter().position(|value| !value.is_finite()) {
+            return Err(KneedleError::NonFinite { row: i, col });
+        }
+    }
 
-    //1) get min and max for each dimension of the data
-    for i in 0..datasize {
-        if data[i].len() != dimensions {
-            return Err("all rows must have the same dimension");
+    Ok(dimensions)
+}
+
+//the weight only depends on the distance between two samples, so compute each one once, a window
+//of 0 only weighs the sample itself (the offset cannot be scaled by it). with a bandwidth, the
+//standard deviation of the kernel in samples, w is only the cut-off, and a bandwidth that is not
+//positive leaves nothing but the sample itself
+fn kernel_weights<T: Float>(w: usize, bandwidth: Option<f64>) -> Vec<T> {
+    let mut weights = Vec::new();
+    fill_kernel_weights(w, bandwidth, &mut weights);
+    weights
+}
+
+//same as kernel_weights, reusing the storage of weights
+pub(crate) fn fill_kernel_weights<T: Float>(
+    w: usize,
+    bandwidth: Option<f64>,
+    weights: &mut Vec<T>,
+) {
+    weights.clear();
+    if w == 0 {
+        weights.push(T::ONE);
+        return;
+    }
+
+    match bandwidth {
+        None => weights.extend((0..=w).map(|offset| index_weight::<T>(0, offset, w))),
+        Some(bandwidth) if bandwidth > 0.0 => {
+            let bandwidth = T::from_f64(bandwidth);
+            weights.extend((0..=w).map(|offset| {
+                gaussian_kernel(T::from_f64(offset as f64), T::ONE, T::ZERO, bandwidth)
+            }));
+        }
+        Some(_) => {
+            weights.push(T::ONE);
+            weights.resize(w + 1, T::ZERO);
+        }
+    }
+}
+
+//smooth row i of data into smoothed_row, weights[d] being the weight of a neighbour d samples away,
+//scaled by point_weights[j] for row j when given. with Boundary::Reflect the part of the window
+//past either end weighs the rows reflected through the end row
+pub(crate) fn smooth_row<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    weights: &[T],
+    point_weights: Option<&[T]>,
+    boundary: Boundary,
+    i: usize,
+    smoothed_row: &mut [T],
+) {
+    let datasize = data.len();
+    let w = weights.len() - 1;
+
+    //the window is cut at either end of the data the same way, w samples either side of i at most
+    let start = i.saturating_sub(w);
+    let end = i.saturating_add(w).min(datasize - 1);
+
+    //accumulate the weighted sums in the output row itself, so smoothing allocates nothing per row
+    smoothed_row.fill(T::ZERO);
+    let mut sum_index_weight = T::ZERO;
+
+    for (j, row) in data.iter().enumerate().take(end + 1).skip(start) {
+        let mut index_weight = weights[i.abs_diff(j)];
+        if let Some(point_weights) = point_weights {
+            index_weight *= point_weights[j];
+        }
+
+        for (sum, &value) in smoothed_row.iter_mut().zip(row.as_ref()) {
+            *sum += index_weight * value;
+        }
+        sum_index_weight += index_weight;
+    }
+
+    if boundary == Boundary::Reflect {
+        //the k-th row past an end is the reflection of the k-th row inside it, 2 * end - row
+        let last = datasize - 1;
+        let mut reflect = |edge: usize, mirror: usize, d: usize| {
+            let mut index_weight = weights[d];
+            if let Some(point_weights) = point_weights {
+                index_weight *= point_weights[mirror];
+            }
+
+            let (edge, mirror) = (data[edge].as_ref(), data[mirror].as_ref());
+            for ((sum, &edge), &value) in smoothed_row.iter_mut().zip(edge).zip(mirror) {
+                *sum += index_weight * (edge + edge - value);
+            }
+            sum_index_weight += index_weight;
+        };
+        for d in i + 1..=w.min(i.saturating_add(last)) {
+            reflect(0, d - i, d);
         }
+        for d in last - i + 1..=w.min((last - i).saturating_add(last)) {
+            reflect(last, last - (d - (last - i)), d);
+        }
+    }
+
+    for smoothed in smoothed_row.iter_mut() {
+        *smoothed = *smoothed / sum_index_weight;
+    }
+}
+
+/// Scales each dimension of `data` into the unit range (step 2 of the paper).
+///
+/// Returns an error for empty data, zero-dimensional rows, rows of differing lengths or NaN and
+/// infinite values. A dimension whose values are all identical has no range to scale by and is
+/// mapped to all zeros, rather than the NaN a plain division would produce.
+pub fn minmax_normalize<T: Float>(mut data: Vec<Vec<T>>) -> Result<Vec<Vec<T>>, KneedleError> {
+    let dimensions = check_rows(&data)?;
+
+    let mut min_each_dimension: Vec<T> = vec![T::MAX; dimensions];
+    let mut max_each_dimension: Vec<T> = vec![T::MIN; dimensions];
+
+    //1) get min and max for each dimension of the data
+    for row in &data {
         for d in 0..dimensions {
-            min_each_dimension[d] = min_each_dimension[d].min(data[i][d]);
-            max_each_dimension[d] = max_each_dimension[d].max(data[i][d]);
+            min_each_dimension[d] = min_each_dimension[d].min(row[d]);
+            max_each_dimension[d] = max_each_dimension[d].max(row[d]);
         }
     }
 
     //2) normalise the data using the min and max
-    let mut range_each_dimension: Vec<f64> = vec![0.0; dimensions];
+    let mut range_each_dimension: Vec<T> = vec![T::ZERO; dimensions];
     for d in 0..dimensions {
         range_each_dimension[d] = max_each_dimension[d] - min_each_dimension[d];
     }
 
-    let mut normalized: Vec<Vec<f64>> = vec![vec![0.0; dimensions]; datasize];
-
-    for i in 0..datasize {
+    //the input is owned, so normalise it in place rather than allocating another copy
+    for row in data.iter_mut() {
         for n in 0..dimensions {
-            normalized[i][n] = (data[i][n] - min_each_dimension[n]) / range_each_dimension[n]
+            if range_each_dimension[n] == T::ZERO {
+                row[n] = T::ZERO;
+                continue;
+            }
+            row[n] = (row[n] - min_each_dimension[n]) / range_each_dimension[n]
+        }
+    }
+
+    Ok(data)
+}
+
+/// Scales each dimension of `data` to mean 0 and standard deviation 1, an alternative to
+/// `minmax_normalize` that a single extreme value distorts less.
+///
+/// Returns the same errors as `minmax_normalize`, and likewise maps a dimension whose values are
+/// all identical to all zeros.
+pub fn zscore_normalize<T: Float>(mut data: Vec<Vec<T>>) -> Result<Vec<Vec<T>>, KneedleError> {
+    let dimensions = check_rows(&data)?;
+    let n = T::from_f64(data.len() as f64);
+
+    for d in 0..dimensions {
+        let mut mean = T::ZERO;
+        for row in &data {
+            mean += row[d];
+        }
+        mean = mean / n;
+
+        let mut variance = T::ZERO;
+        for row in &data {
+            let deviation = row[d] - mean;
+            variance += deviation * deviation;
+        }
+        let std_dev = (variance / n).sqrt();
+
+        for row in data.iter_mut() {
+            row[d] = if std_dev == T::ZERO {
+                T::ZERO
+            } else {
+                (row[d] - mean) / std_dev
+            };
+        }
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_kernel_shape() {
+        for x in [0.1, 0.5, 1.0, 2.5] {
+            assert_eq!(
+                gaussian_kernel(x, 1.5, 0.0, 0.8),
+                gaussian_kernel(-x, 1.5, 0.0, 0.8)
+            );
+            //4 + x and 4 - x round differently, so only up to that rounding
+            let (right, left) = (
+                gaussian_kernel(4.0 + x, 1.5, 4.0, 0.8),
+                gaussian_kernel(4.0 - x, 1.5, 4.0, 0.8),
+            );
+            assert!((right - left).abs() < 1e-12);
+            assert!(gaussian_kernel(4.0 + x, 1.5, 4.0, 0.8) < 1.5);
+        }
+        assert_eq!(1.5, gaussian_kernel(4.0, 1.5, 4.0, 0.8));
+
+        //one standard deviation away
+        let expected = 1.5 * (-0.5f64).exp();
+        assert!((expected - gaussian_kernel(4.8, 1.5, 4.0, 0.8)).abs() < 1e-12);
+        assert!((expected - gaussian_kernel(3.2f32, 1.5, 4.0, 0.8) as f64).abs() < 1e-6);
+    }
+
+    #[test]
+    fn index_weight_decays() {
+        let w = 3;
+        let weight = |j| index_weight::<f64>(5, j, w);
+        let center = weight(5);
+
+        assert!(center > weight(4));
+        assert!(weight(4) > weight(2));
+        assert!(center > weight(5 - w));
+        assert!(center > weight(5 + w));
+        assert_eq!(weight(5 - w), weight(5 + w));
+    }
+
+    #[test]
+    fn gaussian_smooth2d_zero_window() {
+        let data = [[0.0, 0.3], [1.0, 7.0], [2.5, -1.0], [4.0, 0.1]];
+
+        let smoothed = gaussian_smooth2d(&data, 0).unwrap();
+
+        assert_eq!(
+            data.to_vec(),
+            smoothed
+                .iter()
+                .map(|row| [row[0], row[1]])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn gaussian_smooth2d_ragged_row() {
+        let mut data: Vec<Vec<f64>> = (0..10).map(|i| vec![i as f64, i as f64 * 2.0]).collect();
+        data[5].pop();
+
+        let error = gaussian_smooth2d(&data, 1).unwrap_err();
+        assert_eq!(
+            KneedleError::RaggedRows {
+                row: 5,
+                expected: 2,
+                got: 1
+            },
+            error
+        );
+        assert!(error.to_string().contains("row 5"));
+    }
+
+    #[test]
+    fn gaussian_smooth_y_keeps_x() {
+        let data = [
+            [0.0, 1.0],
+            [0.1, 4.0],
+            [0.15, 2.0],
+            [3.0, 8.0],
+            [3.2, 7.0],
+            [10.0, 9.0],
+        ];
+
+        let smoothed = gaussian_smooth_y(&data, 2).unwrap();
+        let all = gaussian_smooth2d(&data, 2).unwrap();
+
+        for ((row, smoothed_row), all_row) in data.iter().zip(&smoothed).zip(&all) {
+            assert_eq!(row[0], smoothed_row[0]);
+            assert_eq!(all_row[1], smoothed_row[1]);
+        }
+        assert_ne!(data[1][0], all[1][0]);
+    }
+
+    #[test]
+    fn bandwidth_apart_from_window() {
+        //a zigzag, whose roughness is the sum of its squared second differences
+        let data: Vec<[f64; 2]> = (0..30)
+            .map(|i| [i as f64, if i % 2 == 0 { 1.0 } else { -1.0 }])
+            .collect();
+        let roughness = |smoothed: &[Vec<f64>]| -> f64 {
+            smoothed
+                .windows(3)
+                .map(|rows| (rows[0][1] - 2.0 * rows[1][1] + rows[2][1]).powi(2))
+                .sum()
+        };
+
+        let w = 6;
+        let mut previous = f64::MAX;
+        for bandwidth in [0.5, 1.0, 1.5, 2.0] {
+            let smoothed = gaussian_smooth2d_with_bandwidth(&data, w, bandwidth).unwrap();
+            let rough = roughness(&smoothed);
+            assert!(rough < previous, "{} at bandwidth {}", rough, bandwidth);
+            previous = rough;
+        }
+
+        //a bandwidth of the window is the kernel of gaussian_smooth2d
+        let same = gaussian_smooth2d_with_bandwidth(&data, w, w as f64).unwrap();
+        for (row, expected) in same.iter().zip(gaussian_smooth2d(&data, w).unwrap()) {
+            assert!((expected[1] - row[1]).abs() < 1e-12);
+        }
+        let unchanged = gaussian_smooth2d_with_bandwidth(&data, w, 0.0).unwrap();
+        assert!(unchanged
+            .iter()
+            .zip(&data)
+            .all(|(row, point)| row[..] == point[..]));
+    }
+
+    #[test]
+    fn reflected_ends_of_a_ramp() {
+        let ramp: Vec<[f64; 2]> = (0..12).map(|i| [i as f64, 3.0 * i as f64 + 1.0]).collect();
+        let w = 3;
+
+        let truncated = gaussian_smooth2d_with_boundary(&ramp, w, Boundary::Truncate).unwrap();
+        let reflected = gaussian_smooth2d_with_boundary(&ramp, w, Boundary::Reflect).unwrap();
+        assert_eq!(gaussian_smooth2d(&ramp, w).unwrap(), truncated);
+
+        //cut off, the ends are pulled towards the interior, reflected they stay on the line
+        assert!(truncated[0][1] > ramp[0][1] + 1.0);
+        assert!(truncated[11][1] < ramp[11][1] - 1.0);
+        for (row, point) in reflected.iter().zip(&ramp) {
+            assert!((row[0] - point[0]).abs() < 1e-12);
+            assert!((row[1] - point[1]).abs() < 1e-12);
+        }
+
+        //away from the ends the two agree
+        for i in w..ramp.len() - w {
+            assert_eq!(truncated[i], reflected[i]);
+        }
+    }
+
+    #[test]
+    fn ema_smooth_y_step() {
+        //a unit step halves its distance to the new level with every row at alpha 0.5
+        let data: Vec<[f64; 2]> = (0..6)
+            .map(|i| [i as f64, if i < 2 { 0.0 } else { 1.0 }])
+            .collect();
+        let smoothed = ema_smooth_y(&data, 0.5).unwrap();
+        let ys: Vec<f64> = smoothed.iter().map(|row| row[1]).collect();
+        assert_eq!(vec![0.0, 0.0, 0.5, 0.75, 0.875, 0.9375], ys);
+        assert!(smoothed
+            .iter()
+            .zip(&data)
+            .all(|(row, point)| row[0] == point[0]));
+
+        for alpha in [0.0, 1.0, f64::NAN] {
+            let unchanged: Vec<Vec<f64>> = data.iter().map(|row| row.to_vec()).collect();
+            assert_eq!(unchanged, ema_smooth_y(&data, alpha).unwrap());
         }
     }
 
-    Ok(normalized)
+    #[test]
+    fn gaussian_smooth_by_x_gap() {
+        //the last row is the next sample but far away in x
+        let data = [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0], [30.0, 10.0]];
+
+        let by_index = gaussian_smooth2d(&data, 1).unwrap();
+        let by_x = gaussian_smooth_by_x(&data, 1.0).unwrap();
+
+        assert!(by_index[3][1] > 1.0);
+        assert_eq!(0.0, by_x[3][1]);
+        assert_eq!(10.0, by_x[4][1]);
+        assert_eq!(30.0, by_x[4][0]);
+
+        //within the bandwidth the neighbours still count
+        let by_x = gaussian_smooth_by_x(&[[0.0, 0.0], [0.5, 3.0], [1.0, 0.0]], 1.0).unwrap();
+        assert!(by_x[1][1] < 3.0);
+        assert!(by_x[0][1] > 0.0);
+    }
+
+    #[test]
+    fn median_smooth_y_spike() {
+        let data = [
+            [0.0, 0.0],
+            [1.0, 1.0],
+            [2.0, 2.0],
+            [3.0, 90.0],
+            [4.0, 4.0],
+            [5.0, 5.0],
+        ];
+
+        let smoothed = median_smooth_y(&data, 3).unwrap();
+
+        let expected = [
+            [0.0, 0.0],
+            [1.0, 1.0],
+            [2.0, 2.0],
+            [3.0, 4.0],
+            [4.0, 5.0],
+            [5.0, 5.0],
+        ];
+        assert_eq!(
+            expected.to_vec(),
+            smoothed
+                .iter()
+                .map(|row| [row[0], row[1]])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Err(KneedleError::EvenWindow(4)), median_smooth_y(&data, 4));
+    }
+
+    #[test]
+    fn savitzky_golay_coefficients_known() {
+        //the classic 5-point quadratic filter, (-3, 12, 17, 12, -3) / 35
+        let coefficients: Vec<f64> = savitzky_golay_coefficients(2, 2);
+        for (c, expected) in coefficients.iter().zip([-3.0, 12.0, 17.0, 12.0, -3.0]) {
+            assert!((c - expected / 35.0).abs() < 1e-12);
+        }
+
+        let data = [[0.0, 1.0], [1.0, 2.0], [2.0, 3.0]];
+        assert_eq!(
+            Err(KneedleError::EvenWindow(4)),
+            savitzky_golay_smooth_y(&data, 4, 2)
+        );
+        assert_eq!(
+            Err(KneedleError::OrderTooHigh {
+                order: 3,
+                window: 3
+            }),
+            savitzky_golay_smooth_y(&data, 3, 3)
+        );
+    }
+
+    #[test]
+    fn gaussian_smooth2d_mirrored_ends() {
+        let data = [[0.0, 1.0], [1.0, 3.0], [2.0, 2.0], [3.0, 8.0], [4.0, 5.0]];
+        let weights: Vec<f64> = kernel_weights(2, None);
+
+        //the first row sees the two rows after it, the last the two before it, with the same weights
+        let smoothed = gaussian_smooth2d(&data, 2).unwrap();
+        let window = |rows: [usize; 3]| {
+            let total: f64 = weights.iter().sum();
+            let y: f64 = rows
+                .iter()
+                .zip(&weights)
+                .map(|(&j, w)| w * data[j][1])
+                .sum();
+            y / total
+        };
+        assert!((smoothed[0][1] - window([0, 1, 2])).abs() < 1e-12);
+        assert!((smoothed[4][1] - window([4, 3, 2])).abs() < 1e-12);
+
+        //so smoothing the mirrored data gives the mirrored result, also for windows wider than
+        //the data
+        for w in [1, 2, 7] {
+            let mirrored: Vec<[f64; 2]> = data.iter().rev().map(|&[x, y]| [4.0 - x, y]).collect();
+            let smoothed = gaussian_smooth2d(&data, w).unwrap();
+            let smoothed_mirrored = gaussian_smooth2d(&mirrored, w).unwrap();
+            for (row, mirrored_row) in smoothed.iter().zip(smoothed_mirrored.iter().rev()) {
+                assert!((row[0] - (4.0 - mirrored_row[0])).abs() < 1e-12);
+                assert!((row[1] - mirrored_row[1]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn smooth_row_past_i32_indices() {
+        //rows of a zero-sized type, so billions of them take no memory, and as a constant array
+        //they are never written one by one
+        #[derive(Clone, Copy)]
+        struct Row;
+        impl AsRef<[f64]> for Row {
+            fn as_ref(&self) -> &[f64] {
+                &[1.0, 2.0]
+            }
+        }
+
+        let data: &[Row] = &[Row; 3_000_000_000];
+        let weights: Vec<f64> = kernel_weights(3, None);
+        for i in [
+            0,
+            i32::MAX as usize - 1,
+            i32::MAX as usize + 2,
+            data.len() - 1,
+        ] {
+            let mut smoothed = [0.0; 2];
+            smooth_row(data, &weights, None, Boundary::Truncate, i, &mut smoothed);
+            assert!((smoothed[0] - 1.0).abs() < 1e-12 && (smoothed[1] - 2.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn gaussian_smooth2d_weight_table() {
+        let data: Vec<[f64; 2]> = (0..50)
+            .map(|i| [i as f64, ((i * 7919) % 13) as f64 + (i as f64).sqrt()])
+            .collect();
+        let w = 4;
+
+        //the kernel evaluated afresh for every pair of samples
+        let mut expected = vec![[0.0; 2]; data.len()];
+        for (i, expected_row) in expected.iter_mut().enumerate() {
+            let start = i.saturating_sub(w);
+            let end = (i + w).min(data.len() - 1);
+            let mut sum_index_weight = 0.0;
+            for (j, row) in data.iter().enumerate().take(end + 1).skip(start) {
+                let weight: f64 = index_weight(i, j, w);
+                expected_row[0] += weight * row[0];
+                expected_row[1] += weight * row[1];
+                sum_index_weight += weight;
+            }
+            expected_row[0] /= sum_index_weight;
+            expected_row[1] /= sum_index_weight;
+        }
+
+        let smoothed = gaussian_smooth2d(&data, w).unwrap();
+        for (row, expected_row) in smoothed.iter().zip(&expected) {
+            assert_eq!(&row[..], &expected_row[..]);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn long_curves_smoothed_in_parallel() {
+        //past PARALLEL_ROWS gaussian_smooth2d takes the threads, and gives every row exactly as
+        //the row loop does
+        let data: Vec<[f64; 2]> = (0..PARALLEL_ROWS + 7)
+            .map(|i| [i as f64, ((i * 7919) % 13) as f64 + (i as f64).sqrt()])
+            .collect();
+        let weights: Vec<f64> = kernel_weights(3, None);
+
+        let smoothed = gaussian_smooth2d_with_boundary(&data, 3, Boundary::Reflect).unwrap();
+        for (i, row) in smoothed.iter().enumerate() {
+            let mut expected = [0.0; 2];
+            smooth_row(&data, &weights, None, Boundary::Reflect, i, &mut expected);
+            assert_eq!(&expected[..], &row[..]);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn gaussian_smooth2d_parallel_matches_serial() {
+        let data: Vec<[f64; 2]> = (0..10_001)
+            .map(|i| [i as f64, ((i * 7919) % 13) as f64 + (i as f64).sqrt()])
+            .collect();
+
+        for w in [1, 3, 10] {
+            assert_eq!(
+                gaussian_smooth2d(&data, w).unwrap(),
+                gaussian_smooth2d_parallel(&data, w).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn minmax_normalize_positive() {
+        let data: Vec<Vec<f64>> = vec![vec![0.0, 5.0], vec![1.0, 7.0], vec![2.0, 50.0]];
+        let normalized = minmax_normalize(data).unwrap();
+
+        assert_eq!(0.0, normalized[0][1]);
+        assert_eq!(1.0, normalized[2][1]);
+    }
+
+    #[test]
+    fn minmax_normalize_constant() {
+        let data: Vec<Vec<f64>> = vec![vec![0.0, 3.0], vec![1.0, 3.0], vec![2.0, 3.0]];
+        let normalized = minmax_normalize(data).unwrap();
+
+        for row in &normalized {
+            assert!(!row[1].is_nan());
+            assert_eq!(0.0, row[1]);
+        }
+        assert_eq!(1.0, normalized[2][0]);
+    }
+
+    #[test]
+    fn zscore_normalize_moments() {
+        let data = vec![
+            vec![1.0, 5.0],
+            vec![2.0, 5.0],
+            vec![3.0, 5.0],
+            vec![10.0, 5.0],
+        ];
+        let normalized = zscore_normalize(data).unwrap();
+
+        let mean: f64 = normalized.iter().map(|row| row[0]).sum::<f64>() / 4.0;
+        let variance: f64 = normalized.iter().map(|row| row[0] * row[0]).sum::<f64>() / 4.0;
+        assert!(mean.abs() < 1e-12);
+        assert!((variance - 1.0).abs() < 1e-12);
+        assert!(normalized.iter().all(|row| row[1] == 0.0));
+
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 1, col: 0 }),
+            zscore_normalize(vec![vec![1.0], vec![f64::NAN]])
+        );
+    }
+
+    #[test]
+    fn minmax_normalize_negative() {
+        let data: Vec<Vec<f64>> = vec![vec![0.0, -50.0], vec![1.0, -7.0], vec![2.0, -5.0]];
+        let normalized = minmax_normalize(data).unwrap();
+
+        assert_eq!(0.0, normalized[0][1]);
+        assert_eq!(1.0, normalized[2][1]);
+    }
 }
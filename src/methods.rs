@@ -0,0 +1,93 @@
+//! Knee detection methods other than Kneedle, to cross-check its results.
+
+use crate::error::KneedleError;
+use crate::float::Float;
+use crate::maths::{gaussian_smooth2d, minmax_normalize};
+use crate::params::KneedleParams;
+use crate::preprocess::rows_for;
+use crate::validate;
+
+/// The point of `data` where the smoothed curve bends the most, the classic maximum curvature
+/// definition of a knee, or `None` when it does not bend anywhere.
+///
+/// The curve is smoothed with `gaussian_smooth2d` over `smoothing_window` samples and scaled into
+/// the unit square, so the result does not depend on the units of x and y. The curvature
+/// `|y''| / (1 + y'^2)^(3/2)` is then taken from three-point finite differences at every interior
+/// point. Unlike Kneedle this finds a single point on any curve and has no sensitivity to tune,
+/// but the second derivative is sensitive to noise, so it suits smooth curves best.
+///
+/// `data` is checked and oriented as `kneedle_with` does with the default parameters: rows must
+/// be finite and distinct in x, descending x is searched in reverse.
+///
+/// ```
+/// let data: Vec<[f64; 2]> = (1..=20).map(|i| [i as f64, -20.0 / i as f64]).collect();
+/// let knee = kneedle::methods::max_curvature_knee(&data, 0).unwrap();
+/// assert_eq!(Some([5.0, -4.0]), knee);
+/// ```
+pub fn max_curvature_knee<T, I>(
+    data: &[I],
+    smoothing_window: usize,
+) -> Result<Option<[T; 2]>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+    let rows = rows_for(data, &KneedleParams::new())?;
+
+    let curve = minmax_normalize(gaussian_smooth2d(&rows.points, smoothing_window)?)?;
+    let point = |i: usize| [curve[i][0].to_f64(), curve[i][1].to_f64()];
+
+    //the square of the curvature has the same maximum and needs no square root
+    let mut best: Option<(usize, f64)> = None;
+    for i in 1..curve.len() - 1 {
+        let [[x0, y0], [x1, y1], [x2, y2]] = [point(i - 1), point(i), point(i + 1)];
+        if x0 == x1 || x1 == x2 {
+            continue;
+        }
+
+        let slope = (y2 - y0) / (x2 - x0);
+        let second = 2.0 * ((y2 - y1) / (x2 - x1) - (y1 - y0) / (x1 - x0)) / (x2 - x0);
+        let base = 1.0 + slope * slope;
+        let curvature_squared = second * second / (base * base * base);
+
+        if curvature_squared > 0.0 && best.is_none_or(|(_, best)| curvature_squared > best) {
+            best = Some((i, curvature_squared));
+        }
+    }
+
+    Ok(best.map(|(i, _)| {
+        let row = data[rows.origin[i]].as_ref();
+        [row[0], row[1]]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kneedle;
+
+    #[test]
+    fn agrees_with_kneedle() {
+        //y = -1/x + 5 for x between 0.1 and 1, as in figure 2 of the paper
+        let test_data: Vec<[f64; 2]> = (2..=20)
+            .map(|i| {
+                let x = i as f64 / 20.0;
+                [x, -1.0 / x + 5.0]
+            })
+            .collect();
+
+        let curvature = max_curvature_knee(&test_data, 1).unwrap().unwrap();
+        let knees = kneedle(&test_data, 1.0, 1, false).unwrap();
+        assert_eq!(1, knees.len());
+        assert!(
+            (curvature[0] - knees[0][0]).abs() <= 0.1,
+            "curvature {:?} kneedle {:?}",
+            curvature,
+            knees[0]
+        );
+
+        let line: Vec<[f64; 2]> = (0..10).map(|i| [i as f64, 2.0 * i as f64]).collect();
+        assert_eq!(None, max_curvature_knee(&line, 1).unwrap());
+    }
+}
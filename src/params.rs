@@ -0,0 +1,382 @@
+/// Shape of the curve being searched, as in the four cases of the paper.
+///
+/// Convex curves are searched for an elbow and concave curves for a knee; decreasing curves are
+/// flipped along x internally before detection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    ConcaveIncreasing,
+    ConcaveDecreasing,
+    ConvexIncreasing,
+    ConvexDecreasing,
+}
+
+impl Curve {
+    pub fn is_convex(self) -> bool {
+        matches!(self, Curve::ConvexIncreasing | Curve::ConvexDecreasing)
+    }
+
+    pub fn is_decreasing(self) -> bool {
+        matches!(self, Curve::ConcaveDecreasing | Curve::ConvexDecreasing)
+    }
+}
+
+/// How the data is smoothed before normalization (step 1 of the paper).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmoothingKind {
+    /// Gaussian kernel over `smoothing_window` samples either side, see `gaussian_smooth2d`, or
+    /// with the width set by `KneedleParams::gaussian_bandwidth`.
+    Gaussian,
+    /// Gaussian kernel over the x-distance between rows, see `gaussian_smooth_by_x`.
+    GaussianDistance { bandwidth: f64 },
+    /// Running median over `window` rows, see `median_smooth_y`.
+    Median { window: usize },
+    /// Savitzky–Golay filter fitting polynomials of degree `order` over `window` rows, see
+    /// `savitzky_golay_smooth_y`.
+    SavitzkyGolay { window: usize, order: usize },
+    /// Exponential moving average of the y-values with weight `alpha` on the newest row, see
+    /// `ema_smooth_y`. `StreamingKneedle` updates it in constant time per point instead of
+    /// smoothing its whole window again.
+    Ema { alpha: f64 },
+}
+
+/// How the Gaussian kernel of `SmoothingKind::Gaussian` treats the part of its window that
+/// extends past either end of the data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// Cut the window off at the end, so the first and last rows are averaged with the interior
+    /// only and lean towards it, as on a ramp whose ends are pulled in.
+    Truncate,
+    /// Continue the data past the end with the rows reflected through the end row, which
+    /// smooths a straight line into itself up to its ends.
+    Reflect,
+}
+
+/// Order of the x-values in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XOrder {
+    /// Ascending x, searched as it is.
+    Ascending,
+    /// Descending x, searched in reverse so callers do not have to flip the data themselves.
+    Descending,
+    /// Descending when the last x is smaller than the first, ascending otherwise.
+    Detect,
+}
+
+/// Scale of the x-axis the knee is searched on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XScale {
+    /// The x-values as they are.
+    Linear,
+    /// The base 10 logarithm of the x-values, for log-spaced data such as parameter sweeps, which
+    /// a linear axis crowds into one end. Every x-value must be positive.
+    Log10,
+}
+
+/// How the smoothed data is scaled before the difference curve is taken (step 2 of the paper).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// Each dimension into the unit range, see `minmax_normalize`.
+    MinMax,
+    /// Each dimension to mean 0 and standard deviation 1, see `zscore_normalize`. A single
+    /// extreme value squeezes the rest of the curve far less than in the unit range. x then spans
+    /// about 3.5 instead of 1 for evenly spaced samples, and the threshold step of the paper, the
+    /// mean gap between normalized x-values, grows with it, so the same `S` drops the threshold
+    /// by the same fraction of the x-range as before, but a different fraction of the y-range.
+    ZScore,
+}
+
+/// How far the difference curve must drop after a candidate for it to count as a knee (step 5
+/// of the paper).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThresholdMode {
+    /// `s` times the mean gap between normalized x-values, as in the paper, which is
+    /// `s / (n - 1)` for `n` evenly spaced rows, so the same `s` asks for a smaller drop on
+    /// denser data.
+    Relative,
+    /// A drop of this size on the normalized difference curve, whatever the spacing of the rows.
+    /// `s` is not used.
+    Absolute(f64),
+    /// `s` times the whole range of the normalized x-values, which is 1 with
+    /// `Normalization::MinMax`. Unlike `Relative` this does not depend on the number of rows, so
+    /// the same `s` confirms the same knees on a curve sampled at any density, but it is not the
+    /// formula of the paper and asks for much smaller values of `s`: 0.1 matches `Relative` with
+    /// `s = 1` on 11 rows.
+    NormalizedSensitivity,
+}
+
+/// Which of the knees that pass the threshold are returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    /// Every knee.
+    All,
+    /// Only the strongest knee, the one `kneedle_ranked` puts first: the largest magnitude of
+    /// the difference curve, the smaller x of two as large.
+    FirstMostProminent,
+    /// Only the last of the knees `All` returns, the one of largest x on ascending data.
+    Last,
+}
+
+/// Scale of the y-axis the knee is searched on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YScale {
+    /// The y-values as they are.
+    Linear,
+    /// The base 10 logarithm of the y-values, for curves that approach their asymptote
+    /// exponentially, whose bend a linear axis squeezes against the asymptote. Every y-value must
+    /// be positive.
+    Log10,
+}
+
+/// What to do with consecutive rows that share the same x-value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateX {
+    /// Fail with `KneedleError::DuplicateX`.
+    Error,
+    /// Keep the first row of each run of equal x-values.
+    KeepFirst,
+    /// Collapse each run into one row at the mean of its y-values.
+    Average,
+}
+
+/// What to do with rows holding a NaN or infinite value, which break the comparisons of the
+/// search and the min-max normalization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Fail with `KneedleError::NonFinite`.
+    Error,
+    /// Leave those rows out of the search.
+    Skip,
+}
+
+/// Named parameters for `kneedle_with`.
+///
+/// ```
+/// use kneedle::{Curve, KneedleParams};
+///
+/// let params = KneedleParams::new().sensitivity(1.0).smoothing_window(3).elbow(true);
+/// assert_eq!(Curve::ConvexIncreasing, params.curve);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct KneedleParams {
+    /// Sensitivity `S` of the paper, larger values make detection more conservative.
+    pub s: f64,
+    /// How the threshold is derived, from `s` or given directly.
+    pub threshold: ThresholdMode,
+    /// Half-width of the Gaussian smoothing window, in samples.
+    pub smoothing_window: usize,
+    /// Standard deviation of the Gaussian kernel in samples, leaving `smoothing_window` only the
+    /// cut-off, see `gaussian_smooth2d_with_bandwidth`. `None` makes it the window itself.
+    pub gaussian_bandwidth: Option<f64>,
+    /// Treatment of the ends of the data by the Gaussian smoothing.
+    pub boundary: Boundary,
+    /// The smoother applied before normalization.
+    pub smoothing: SmoothingKind,
+    /// The scaling of the smoothed data.
+    pub normalization: Normalization,
+    /// With `SmoothingKind::Gaussian`, smooth only the y-values and leave the x-values as they are.
+    /// The other kinds of smoothing always keep the x-values.
+    pub smooth_y_only: bool,
+    /// Shape of the curve, which decides between knee and elbow detection.
+    pub curve: Curve,
+    /// Smallest distance in x between two knees, of two closer knees only the stronger one is
+    /// kept. 0 keeps every knee.
+    pub min_separation: f64,
+    /// Which of the knees found are returned, after those closer than `min_separation` are
+    /// dropped.
+    pub selection: Selection,
+    /// Also take the first and last points for candidates, so a knee at either end of the
+    /// difference curve can be found. The last point is kept whenever it is a candidate, as no
+    /// point after it can cross the threshold.
+    pub include_endpoints: bool,
+    /// Smallest margin by which a point of the difference curve must exceed both its neighbours,
+    /// or fall below them for elbows, to be a candidate. The difference curve lies in the unit
+    /// range, so a small margin such as 1e-6 ignores floating-point wobble in flat regions. 0
+    /// takes every strict extremum.
+    pub extremum_tolerance: f64,
+    /// Reject curves whose y-values are not monotonic with `KneedleError::NonMonotonic`, allowing
+    /// steps against the direction of the curve up to this fraction of the range of y. `None`
+    /// searches any curve.
+    pub monotonic_tolerance: Option<f64>,
+    /// Order of the x-values, knee indices always refer to the rows as given.
+    pub x_order: XOrder,
+    /// Scale of the x-axis, knee points are always returned in the units of `data`.
+    pub x_scale: XScale,
+    /// Scale of the y-axis, knee points are always returned in the units of `data`.
+    pub y_scale: YScale,
+    /// Sort the rows by x before searching, for input that is not already in ascending x order.
+    pub sort_by_x: bool,
+    /// Handling of repeated x-values, which leave the difference curve ill-defined.
+    pub duplicate_x: DuplicateX,
+    /// Handling of NaN and infinite values.
+    pub non_finite: NonFinite,
+    /// Drop the leading rows whose y stays within `flat_tolerance` of the first one, such as a
+    /// warm-up before the curve starts, keeping the last of them. Their flat end would otherwise
+    /// bend into the curve and show up as a knee of its own.
+    pub trim_flat_prefix: bool,
+    /// The same for the trailing rows whose y stays within `flat_tolerance` of the last one.
+    pub trim_flat_suffix: bool,
+    /// How far y may move and still count as flat for `trim_flat_prefix` and `trim_flat_suffix`,
+    /// as a fraction of the range of y.
+    pub flat_tolerance: f64,
+}
+
+impl Default for KneedleParams {
+    fn default() -> Self {
+        KneedleParams {
+            s: 1.0,
+            threshold: ThresholdMode::Relative,
+            smoothing_window: 3,
+            gaussian_bandwidth: None,
+            boundary: Boundary::Truncate,
+            smoothing: SmoothingKind::Gaussian,
+            normalization: Normalization::MinMax,
+            smooth_y_only: false,
+            curve: Curve::ConcaveIncreasing,
+            min_separation: 0.0,
+            selection: Selection::All,
+            include_endpoints: false,
+            extremum_tolerance: 0.0,
+            monotonic_tolerance: None,
+            x_order: XOrder::Detect,
+            x_scale: XScale::Linear,
+            y_scale: YScale::Linear,
+            sort_by_x: false,
+            duplicate_x: DuplicateX::Error,
+            non_finite: NonFinite::Error,
+            trim_flat_prefix: false,
+            trim_flat_suffix: false,
+            flat_tolerance: 0.0,
+        }
+    }
+}
+
+impl KneedleParams {
+    /// Knee detection on a concave increasing curve with S = 1 and a smoothing window of 3.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sensitivity(mut self, s: f64) -> Self {
+        self.s = s;
+        self
+    }
+
+    pub fn threshold(mut self, threshold: ThresholdMode) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn smoothing_window(mut self, smoothing_window: usize) -> Self {
+        self.smoothing_window = smoothing_window;
+        self
+    }
+
+    pub fn gaussian_bandwidth(mut self, gaussian_bandwidth: Option<f64>) -> Self {
+        self.gaussian_bandwidth = gaussian_bandwidth;
+        self
+    }
+
+    pub fn boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn smoothing(mut self, smoothing: SmoothingKind) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    pub fn smooth_y_only(mut self, smooth_y_only: bool) -> Self {
+        self.smooth_y_only = smooth_y_only;
+        self
+    }
+
+    pub fn curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    pub fn min_separation(mut self, min_separation: f64) -> Self {
+        self.min_separation = min_separation;
+        self
+    }
+
+    pub fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn include_endpoints(mut self, include_endpoints: bool) -> Self {
+        self.include_endpoints = include_endpoints;
+        self
+    }
+
+    pub fn extremum_tolerance(mut self, extremum_tolerance: f64) -> Self {
+        self.extremum_tolerance = extremum_tolerance;
+        self
+    }
+
+    pub fn monotonic_tolerance(mut self, monotonic_tolerance: Option<f64>) -> Self {
+        self.monotonic_tolerance = monotonic_tolerance;
+        self
+    }
+
+    pub fn x_order(mut self, x_order: XOrder) -> Self {
+        self.x_order = x_order;
+        self
+    }
+
+    pub fn x_scale(mut self, x_scale: XScale) -> Self {
+        self.x_scale = x_scale;
+        self
+    }
+
+    pub fn y_scale(mut self, y_scale: YScale) -> Self {
+        self.y_scale = y_scale;
+        self
+    }
+
+    pub fn sort_by_x(mut self, sort_by_x: bool) -> Self {
+        self.sort_by_x = sort_by_x;
+        self
+    }
+
+    pub fn duplicate_x(mut self, duplicate_x: DuplicateX) -> Self {
+        self.duplicate_x = duplicate_x;
+        self
+    }
+
+    pub fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    pub fn trim_flat_prefix(mut self, trim_flat_prefix: bool) -> Self {
+        self.trim_flat_prefix = trim_flat_prefix;
+        self
+    }
+
+    pub fn trim_flat_suffix(mut self, trim_flat_suffix: bool) -> Self {
+        self.trim_flat_suffix = trim_flat_suffix;
+        self
+    }
+
+    pub fn flat_tolerance(mut self, flat_tolerance: f64) -> Self {
+        self.flat_tolerance = flat_tolerance;
+        self
+    }
+
+    /// Shorthand for an increasing curve, convex when `find_elbow` is set and concave otherwise.
+    pub fn elbow(self, find_elbow: bool) -> Self {
+        if find_elbow {
+            self.curve(Curve::ConvexIncreasing)
+        } else {
+            self.curve(Curve::ConcaveIncreasing)
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::error::KneedleError;
+use crate::float::Float;
+use crate::params::KneedleParams;
+use crate::preprocess::rows_for;
+use crate::{select_knees, separate_knees, smooth, validate, KneedleWorkspace, Point};
+
+const WIDTH: f64 = 640.0;
+const PANEL_HEIGHT: f64 = 240.0;
+const MARGIN: f64 = 40.0;
+
+/// Draws the steps of the detection on `data` with `params` as an SVG image, for seeing why a
+/// knee landed where it did.
+///
+/// The top panel holds the input and its smoothed curve, the bottom one the normalized
+/// difference curve the search runs on, oriented as it is searched, so decreasing curves appear
+/// flipped. Every detected knee is marked by a vertical line in both panels. With
+/// `XScale::Log10` the x-axis of the top panel is the logarithm of x, and likewise for
+/// `YScale::Log10`.
+pub fn render_debug_svg<T, I>(data: &[I], params: &KneedleParams) -> Result<String, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    validate(data)?;
+
+    let rows = rows_for(data, params)?;
+    let points = to_f64(&rows.points);
+    let smoothed: Vec<Point> = smooth(&rows.points, params)?
+        .iter()
+        .map(|row| [row[0].to_f64(), row[1].to_f64()])
+        .collect();
+
+    let mut workspace = KneedleWorkspace::new();
+    workspace.find_oriented_knees(&rows.points, None, params)?;
+    let knees = separate_knees(&rows.points, workspace.knees, params.min_separation);
+    let knees = select_knees(&rows.points, knees, params.selection);
+
+    //the difference curve as the search sees it, decreasing curves mirrored along x
+    let n = rows.points.len();
+    let decreasing = params.curve.is_decreasing();
+    let searched: Vec<[T; 2]> = if decreasing {
+        let xmax = rows
+            .points
+            .iter()
+            .fold(T::MIN, |xmax, point| xmax.max(point[0]));
+        rows.points
+            .iter()
+            .rev()
+            .map(|point| [xmax - point[0], point[1]])
+            .collect()
+    } else {
+        rows.points.clone()
+    };
+    let difference = to_f64(KneedleWorkspace::new().prepare(&searched, params)?);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+        WIDTH,
+        2.0 * PANEL_HEIGHT
+    );
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+
+    let top = Panel::new(0.0, points.iter().chain(&smoothed));
+    top.axes(&mut svg, "data (grey) and smoothed (blue)");
+    top.polyline(&mut svg, &points, "grey");
+    top.polyline(&mut svg, &smoothed, "steelblue");
+
+    let bottom = Panel::new(PANEL_HEIGHT, difference.iter());
+    bottom.axes(&mut svg, "normalized difference curve");
+    bottom.polyline(&mut svg, &difference, "darkorange");
+
+    for knee in &knees {
+        let searched_index = if decreasing {
+            n - 1 - knee.index
+        } else {
+            knee.index
+        };
+        top.marker(&mut svg, points[knee.index][0]);
+        bottom.marker(&mut svg, difference[searched_index][0]);
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Same as `render_debug_svg`, writing the image to the file at `out_path`.
+pub fn render_debug<T, I, P>(
+    data: &[I],
+    params: &KneedleParams,
+    out_path: P,
+) -> Result<(), KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+    P: AsRef<Path>,
+{
+    let svg = render_debug_svg(data, params)?;
+    std::fs::write(out_path, svg).map_err(|error| KneedleError::Io(error.kind()))
+}
+
+fn to_f64<T: Float>(points: &[[T; 2]]) -> Vec<Point> {
+    points
+        .iter()
+        .map(|point| [point[0].to_f64(), point[1].to_f64()])
+        .collect()
+}
+
+//one plot of the image, mapping data coordinates onto its area below offset
+struct Panel {
+    offset: f64,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+}
+
+impl Panel {
+    fn new<'a>(offset: f64, points: impl Iterator<Item = &'a Point>) -> Self {
+        let (mut x_range, mut y_range) = ((f64::MAX, f64::MIN), (f64::MAX, f64::MIN));
+        for &[x, y] in points {
+            x_range = (x_range.0.min(x), x_range.1.max(x));
+            y_range = (y_range.0.min(y), y_range.1.max(y));
+        }
+        Panel {
+            offset,
+            x_range,
+            y_range,
+        }
+    }
+
+    //a constant range is drawn in the middle of the panel rather than dividing by zero
+    fn scale(value: f64, (min, max): (f64, f64)) -> f64 {
+        if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.5
+        }
+    }
+
+    fn x(&self, x: f64) -> f64 {
+        MARGIN + Self::scale(x, self.x_range) * (WIDTH - 2.0 * MARGIN)
+    }
+
+    fn y(&self, y: f64) -> f64 {
+        self.offset + PANEL_HEIGHT
+            - MARGIN
+            - Self::scale(y, self.y_range) * (PANEL_HEIGHT - 2.0 * MARGIN)
+    }
+
+    fn axes(&self, svg: &mut String, title: &str) {
+        let (left, right) = (MARGIN, WIDTH - MARGIN);
+        let (top, bottom) = (self.offset + MARGIN, self.offset + PANEL_HEIGHT - MARGIN);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            left,
+            top,
+            right - left,
+            bottom - top
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="12">{}</text>"#,
+            left,
+            top - 8.0,
+            title
+        );
+        for (value, x, anchor) in [
+            (self.x_range.0, left, "start"),
+            (self.x_range.1, right, "end"),
+        ] {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="10" text-anchor="{}">{:.4}</text>"#,
+                x,
+                bottom + 14.0,
+                anchor,
+                value
+            );
+        }
+        for (value, y) in [(self.y_range.0, bottom), (self.y_range.1, top)] {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="10" text-anchor="end">{:.4}</text>"#,
+                left - 4.0,
+                y,
+                value
+            );
+        }
+    }
+
+    fn polyline(&self, svg: &mut String, points: &[Point], color: &str) {
+        svg.push_str("<polyline fill=\"none\" stroke=\"");
+        svg.push_str(color);
+        svg.push_str("\" points=\"");
+        for &[x, y] in points {
+            let _ = write!(svg, "{:.2},{:.2} ", self.x(x), self.y(y));
+        }
+        svg.push_str("\"/>\n");
+    }
+
+    fn marker(&self, svg: &mut String, x: f64) {
+        let _ = writeln!(
+            svg,
+            r#"<line class="knee" x1="{x:.2}" y1="{}" x2="{x:.2}" y2="{}" stroke="red" stroke-dasharray="4 2"/>"#,
+            self.offset + MARGIN,
+            self.offset + PANEL_HEIGHT - MARGIN,
+            x = self.x(x)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Curve;
+
+    #[test]
+    fn draws_every_step() {
+        let test_data = [
+            [0.0, 0.0],
+            [0.1, 0.55],
+            [0.2, 0.75],
+            [0.35, 0.825],
+            [0.45, 0.875],
+            [0.55, 0.9],
+            [0.675, 0.925],
+            [0.775, 0.95],
+            [0.875, 0.975],
+            [1.0, 1.0],
+        ];
+        let params = KneedleParams::new().smoothing_window(1);
+
+        let svg = render_debug_svg(&test_data, &params).unwrap();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(3, svg.matches("<polyline").count());
+        assert_eq!(2, svg.matches("class=\"knee\"").count());
+
+        //a decreasing curve without a knee still draws its curves
+        let flat: Vec<Point> = (0..5).map(|i| [i as f64, 4.0 - i as f64]).collect();
+        let svg = render_debug_svg(&flat, &params.clone().curve(Curve::ConvexDecreasing)).unwrap();
+        assert_eq!(3, svg.matches("<polyline").count());
+        assert_eq!(0, svg.matches("class=\"knee\"").count());
+
+        let path = std::env::temp_dir().join("kneedle_draws_every_step.svg");
+        render_debug(&test_data, &params, &path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(render_debug_svg(&test_data, &params).unwrap(), written);
+
+        assert_eq!(
+            Err(KneedleError::Io(std::io::ErrorKind::NotFound)),
+            render_debug(&test_data, &params, "/nonexistent/directory/knee.svg")
+        );
+    }
+}
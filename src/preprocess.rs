@@ -0,0 +1,307 @@
+use alloc::vec::Vec;
+
+use crate::clean_rows;
+use crate::error::KneedleError;
+use crate::float::Float;
+use crate::params::{DuplicateX, KneedleParams, NonFinite, XOrder, XScale, YScale};
+
+//a working copy of the input for the options that reorder or drop rows, origin[i] being the
+//position in the input of points[i]
+pub(crate) struct Rows<T> {
+    pub(crate) points: Vec<[T; 2]>,
+    pub(crate) origin: Vec<usize>,
+}
+
+//the rows of data as params has them searched, a plain copy when clean_rows leaves them as they are
+pub(crate) fn rows_for<T, I>(data: &[I], params: &KneedleParams) -> Result<Rows<T>, KneedleError>
+where
+    T: Float,
+    I: AsRef<[T]>,
+{
+    Ok(match clean_rows(data, params)? {
+        Some(rows) => rows,
+        None => Rows::new(data),
+    })
+}
+
+//whether params leave the rows of increasing data as they are, only checking them
+pub(crate) fn keeps_rows(params: &KneedleParams) -> bool {
+    !params.sort_by_x
+        && params.duplicate_x == DuplicateX::Error
+        && params.non_finite == NonFinite::Error
+        && params.x_scale == XScale::Linear
+        && params.y_scale == YScale::Linear
+        && !params.trim_flat_prefix
+        && !params.trim_flat_suffix
+}
+
+impl<T: Float> Rows<T> {
+    pub(crate) fn new<I: AsRef<[T]>>(data: &[I]) -> Self {
+        Rows {
+            points: data
+                .iter()
+                .map(|row| [row.as_ref()[0], row.as_ref()[1]])
+                .collect(),
+            origin: (0..data.len()).collect(),
+        }
+    }
+
+    //reject or drop the rows holding a NaN or infinite value
+    pub(crate) fn filter_non_finite(&mut self, policy: NonFinite) -> Result<(), KneedleError> {
+        if policy == NonFinite::Error {
+            return match first_non_finite(&self.points) {
+                Some((i, col)) => Err(KneedleError::NonFinite {
+                    row: self.origin[i],
+                    col,
+                }),
+                None => Ok(()),
+            };
+        }
+
+        let mut kept = 0;
+        for i in 0..self.points.len() {
+            if self.points[i].iter().all(|value| value.is_finite()) {
+                self.points[kept] = self.points[i];
+                self.origin[kept] = self.origin[i];
+                kept += 1;
+            }
+        }
+        self.points.truncate(kept);
+        self.origin.truncate(kept);
+        Ok(())
+    }
+
+    //replace every x-value by its base 10 logarithm, which only exists for positive x
+    pub(crate) fn log10_x(&mut self) -> Result<(), KneedleError> {
+        for (point, &origin) in self.points.iter_mut().zip(&self.origin) {
+            if point[0].is_nan() || point[0] <= T::ZERO {
+                return Err(KneedleError::NonPositiveX { row: origin });
+            }
+            point[0] = point[0].log10();
+        }
+        Ok(())
+    }
+
+    //the same for the y-values
+    pub(crate) fn log10_y(&mut self) -> Result<(), KneedleError> {
+        for (point, &origin) in self.points.iter_mut().zip(&self.origin) {
+            if point[1].is_nan() || point[1] <= T::ZERO {
+                return Err(KneedleError::NonPositiveY { row: origin });
+            }
+            point[1] = point[1].log10();
+        }
+        Ok(())
+    }
+
+    //drop the flat runs of y at the start and the end, each but its innermost row. a curve that
+    //is flat throughout is kept whole, it has no knee either way
+    pub(crate) fn trim_flat(&mut self, prefix: bool, suffix: bool, tolerance: f64) {
+        let n = self.points.len();
+        if n == 0 || !(prefix || suffix) {
+            return;
+        }
+
+        let (mut y_min, mut y_max) = (T::MAX, T::MIN);
+        for point in &self.points {
+            y_min = y_min.min(point[1]);
+            y_max = y_max.max(point[1]);
+        }
+        let allowed = T::from_f64(tolerance.max(0.0)) * (y_max - y_min);
+        let flat = |i: usize, j: usize| (self.points[i][1] - self.points[j][1]).abs() <= allowed;
+
+        let first = (1..n).find(|&i| !flat(i, 0)).map_or(n, |i| i - 1);
+        if first == n {
+            return;
+        }
+        let last = (0..n - 1)
+            .rev()
+            .find(|&i| !flat(i, n - 1))
+            .map_or(0, |i| i + 1);
+
+        let start = if prefix { first } else { 0 };
+        let end = if suffix { last } else { n - 1 };
+        //with a tolerance so wide that the two runs overlap, there is no curve between them
+        if start > end {
+            return;
+        }
+        self.points.truncate(end + 1);
+        self.origin.truncate(end + 1);
+        self.points.drain(..start);
+        self.origin.drain(..start);
+    }
+
+    pub(crate) fn reverse(&mut self) {
+        self.points.reverse();
+        self.origin.reverse();
+    }
+
+    //stable sort by x, NaN x-values cannot be ordered so they are rejected
+    pub(crate) fn sort_by_x(&mut self) -> Result<(), KneedleError> {
+        if let Some(i) = self.points.iter().position(|point| point[0].is_nan()) {
+            return Err(KneedleError::NonFinite {
+                row: self.origin[i],
+                col: 0,
+            });
+        }
+
+        let mut order: Vec<usize> = (0..self.points.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.points[a][0]
+                .partial_cmp(&self.points[b][0])
+                .expect("NaN x-values were rejected above")
+        });
+        self.points = order.iter().map(|&i| self.points[i]).collect();
+        self.origin = order.iter().map(|&i| self.origin[i]).collect();
+        Ok(())
+    }
+
+    //collapse runs of equal consecutive x-values, a collapsed run keeps the origin of its first row
+    pub(crate) fn dedup_x(&mut self, policy: DuplicateX) -> Result<(), KneedleError> {
+        if policy == DuplicateX::Error {
+            return match first_duplicate_x(&self.points) {
+                Some(i) => Err(KneedleError::DuplicateX {
+                    row: self.origin[i],
+                }),
+                None => Ok(()),
+            };
+        }
+
+        let mut points: Vec<[T; 2]> = Vec::with_capacity(self.points.len());
+        let mut origin = Vec::with_capacity(self.points.len());
+        let mut i = 0;
+        while i < self.points.len() {
+            let x = self.points[i][0];
+            let mut end = i + 1;
+            while end < self.points.len() && self.points[end][0] == x {
+                end += 1;
+            }
+
+            let y = match policy {
+                DuplicateX::Average => {
+                    let mut sum = T::ZERO;
+                    for point in &self.points[i..end] {
+                        sum += point[1];
+                    }
+                    sum / T::from_f64((end - i) as f64)
+                }
+                _ => self.points[i][1],
+            };
+            points.push([x, y]);
+            origin.push(self.origin[i]);
+            i = end;
+        }
+
+        self.points = points;
+        self.origin = origin;
+        Ok(())
+    }
+}
+
+//whether data has to be reversed to put its x-values in ascending order
+pub(crate) fn is_descending<T: Float, I: AsRef<[T]>>(data: &[I], x_order: XOrder) -> bool {
+    match x_order {
+        XOrder::Ascending => false,
+        XOrder::Descending => true,
+        XOrder::Detect => match (data.first(), data.last()) {
+            (Some(first), Some(last)) => last.as_ref()[0] < first.as_ref()[0],
+            _ => false,
+        },
+    }
+}
+
+//row and column of the first NaN or infinite value
+pub(crate) fn first_non_finite<T: Float, I: AsRef<[T]>>(data: &[I]) -> Option<(usize, usize)> {
+    data.iter().enumerate().find_map(|(row, values)| {
+        values
+            .as_ref()
+            .iter()
+            .position(|value| !value.is_finite())
+            .map(|col| (row, col))
+    })
+}
+
+//index of the first row whose x equals the x of the row before it
+pub(crate) fn first_duplicate_x<T: Float, I: AsRef<[T]>>(data: &[I]) -> Option<usize> {
+    (1..data.len()).find(|&i| data[i].as_ref()[0] == data[i - 1].as_ref()[0])
+}
+
+//index of the first row whose y steps against the direction of the curve by more than tolerance
+//times the range of y. the direction is that from the first y to the last, or of the first step
+//beyond the tolerance when they are equal
+pub(crate) fn first_non_monotonic<T: Float, I: AsRef<[T]>>(
+    data: &[I],
+    tolerance: f64,
+) -> Option<usize> {
+    let y = |i: usize| data[i].as_ref()[1].to_f64();
+    let (mut y_min, mut y_max) = (f64::MAX, f64::MIN);
+    for i in 0..data.len() {
+        y_min = y_min.min(y(i));
+        y_max = y_max.max(y(i));
+    }
+    let allowed = tolerance.max(0.0) * (y_max - y_min);
+
+    let mut increasing = match data.len() {
+        0 => return None,
+        n if y(n - 1) != y(0) => Some(y(n - 1) > y(0)),
+        _ => None,
+    };
+    for i in 1..data.len() {
+        let step = y(i) - y(i - 1);
+        if step.abs() <= allowed {
+            continue;
+        }
+        match increasing {
+            Some(increasing) if increasing != (step > 0.0) => return Some(i),
+            Some(_) => {}
+            None => increasing = Some(step > 0.0),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_non_finite() {
+        let data = [
+            [0.0, 1.0],
+            [1.0, f64::NAN],
+            [f64::INFINITY, 2.0],
+            [3.0, 3.0],
+        ];
+
+        let mut rows = Rows::new(&data);
+        assert_eq!(
+            Err(KneedleError::NonFinite { row: 1, col: 1 }),
+            rows.filter_non_finite(NonFinite::Error)
+        );
+
+        let mut rows = Rows::new(&data);
+        rows.filter_non_finite(NonFinite::Skip).unwrap();
+        assert_eq!(vec![[0.0, 1.0], [3.0, 3.0]], rows.points);
+        assert_eq!(vec![0, 3], rows.origin);
+    }
+
+    #[test]
+    fn dedup_x() {
+        let data = [[1.0, 2.0], [1.0, 3.0], [2.0, 5.0]];
+
+        let mut rows = Rows::new(&data);
+        assert_eq!(
+            Err(KneedleError::DuplicateX { row: 1 }),
+            rows.dedup_x(DuplicateX::Error)
+        );
+
+        let mut rows = Rows::new(&data);
+        rows.dedup_x(DuplicateX::KeepFirst).unwrap();
+        assert_eq!(vec![[1.0, 2.0], [2.0, 5.0]], rows.points);
+        assert_eq!(vec![0, 2], rows.origin);
+
+        let mut rows = Rows::new(&data);
+        rows.dedup_x(DuplicateX::Average).unwrap();
+        assert_eq!(vec![[1.0, 2.5], [2.0, 5.0]], rows.points);
+        assert_eq!(vec![0, 2], rows.origin);
+    }
+}
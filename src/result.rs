@@ -0,0 +1,102 @@
+use alloc::vec::Vec;
+
+use crate::Point;
+
+/// The knee found most consistently by `kneedle_sweep`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepResult {
+    pub knee: KneeResult,
+    /// Fraction of the swept sensitivities that found this knee.
+    pub agreement: f64,
+}
+
+/// A detected knee/elbow point together with the signal behind its detection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KneeResult {
+    pub x: f64,
+    pub y: f64,
+    /// Position of the point in the input data.
+    pub index: usize,
+    /// Height of the normalized difference curve at the knee.
+    pub diff_value: f64,
+    /// The height the difference curve crossed after the knee to confirm it, `diff_value` plus
+    /// the signed `KneedleTrace::step`.
+    pub threshold: f64,
+    /// How far the knee stands out of the difference curve around it, in the units of the
+    /// difference curve.
+    ///
+    /// With `Normalization::MinMax` and a monotonic curve this lies between 0 for a barely
+    /// noticeable bend and 1 for the sharpest possible corner. A curve that turns back can score
+    /// more, and so can any curve under `Normalization::ZScore`, whose difference curve is not
+    /// bounded. The score is reported as it is, not cut off at 1, so stronger knees still rank
+    /// higher.
+    pub prominence: f64,
+}
+
+impl KneeResult {
+    /// Whether `other` is the same knee up to `eps` in each of its values, for comparing
+    /// detections that ran in a different precision or order of operations, where `==` is
+    /// too strict. The indices must match exactly.
+    pub fn approx_eq(&self, other: &KneeResult, eps: f64) -> bool {
+        let close = |a: f64, b: f64| (a - b).abs() <= eps;
+        self.index == other.index
+            && close(self.x, other.x)
+            && close(self.y, other.y)
+            && close(self.diff_value, other.diff_value)
+            && close(self.threshold, other.threshold)
+            && close(self.prominence, other.prominence)
+    }
+}
+
+/// A candidate of step 4 and how it fared against its threshold in step 6, as
+/// `candidate_report` returns it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CandidateInfo {
+    /// Position of the point in the input data.
+    pub index: usize,
+    /// Height of the normalized difference curve at the candidate.
+    pub diff_height: f64,
+    /// The height the difference curve had to fall below after the candidate, or rise above for
+    /// an elbow, before the next candidate.
+    pub threshold: f64,
+    /// Whether the candidate was confirmed as a knee.
+    pub passed: bool,
+}
+
+/// Every stage of one detection, as `kneedle_trace` returns it, for comparing runs while tuning
+/// the parameters.
+///
+/// The stages before `knees` are in the orientation the search runs in: a decreasing curve is
+/// flipped along x into an increasing one, and `candidates` index into its rows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KneedleTrace {
+    /// The rows searched, after the clean-up chosen in the parameters.
+    pub raw: Vec<Point>,
+    /// `raw` after step 1 of the paper.
+    pub smoothed: Vec<Point>,
+    /// `smoothed` scaled into the unit square, step 2.
+    pub normalized: Vec<Point>,
+    /// The difference curve of step 3, `[x, y - x]` of `normalized`.
+    pub difference: Vec<Point>,
+    /// Local extrema of `difference` tested against the threshold, step 4.
+    pub candidates: Vec<usize>,
+    /// What the threshold of each candidate adds to its height in `difference`, step 5: the mean
+    /// x-step times `S`, the absolute drop, or with `ThresholdMode::NormalizedSensitivity` the
+    /// span of the normalized x-values times `S`, negative for knees and positive for elbows.
+    pub step: f64,
+    /// The knee points of the input, as `kneedle_with` returns them.
+    pub knees: Vec<Point>,
+}
+
+/// The spread of the knee over the bootstrap resamples of `kneedle_bootstrap`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BootstrapResult {
+    /// Median x of the knees of the resamples.
+    pub median_x: f64,
+    /// Lower end of the central 95% of the knee x-values.
+    pub ci_low: f64,
+    /// Upper end of the central 95% of the knee x-values.
+    pub ci_high: f64,
+    /// Number of resamples that found a knee.
+    pub found: usize,
+}
@@ -0,0 +1,347 @@
+use alloc::collections::VecDeque;
+use alloc::vec::{self, Vec};
+
+use crate::maths::{ema_step, fill_kernel_weights, smooth_row};
+use crate::preprocess::{first_duplicate_x, first_non_monotonic, keeps_rows};
+use crate::{
+    kneedle_ranked, kneedle_with, KneeResult, KneedleError, KneedleParams, Point, SmoothingKind,
+};
+
+/// Knee detection over a sliding window of the most recent points of a stream.
+///
+/// Every `push` searches the buffered points again, normalizing them as a whole, which costs
+/// `O(capacity)`. A larger buffer sees more of the curve and gives a more stable knee, but reacts
+/// later to a change in the stream and costs more per push; a small buffer follows the stream
+/// closely but only ever sees a local piece of the curve.
+///
+/// With `SmoothingKind::Gaussian` the smoothed points are kept along with the buffer, and a push
+/// only smooths again the rows within `smoothing_window` of either end of it, the ones whose
+/// window gained the new point or lost the oldest one. The options that clean up the rows before they
+/// are smoothed, sorting, dropping or merging rows and the logarithmic scales, leave nothing to
+/// carry along, and with them, as with the median, Savitzky–Golay and distance smoothing, every
+/// push smooths the whole buffer again at `O(capacity * smoothing_window)`.
+///
+/// With `SmoothingKind::Ema` the moving average is carried along the stream instead, updated
+/// once per push. The average remembers points that have left the window, and lags behind the
+/// stream as `ema_smooth_y` describes, so a knee is reported a few points late.
+#[derive(Clone, Debug)]
+pub struct StreamingKneedle {
+    buffer: VecDeque<Point>,
+    //each buffered point smoothed, with SmoothingKind::Ema or a Gaussian that keeps the rows
+    smoothed: VecDeque<Point>,
+    //the kernel of the Gaussian smoothing, weights[d] for a neighbour d rows away
+    weights: Vec<f64>,
+    capacity: usize,
+    params: KneedleParams,
+    knee: Option<Point>,
+}
+
+impl StreamingKneedle {
+    /// Keeps the last `capacity` points and searches them with `params`.
+    pub fn new(capacity: usize, params: KneedleParams) -> Self {
+        let mut weights = Vec::new();
+        fill_kernel_weights(
+            params.smoothing_window,
+            params.gaussian_bandwidth,
+            &mut weights,
+        );
+        StreamingKneedle {
+            buffer: VecDeque::with_capacity(capacity + 1),
+            smoothed: VecDeque::new(),
+            weights,
+            capacity,
+            params,
+            knee: None,
+        }
+    }
+
+    /// Adds a point to the end of the window, dropping the oldest one once the window is full.
+    pub fn push(&mut self, point: Point) {
+        self.buffer.push_back(point);
+        let dropped = self.buffer.len() > self.capacity;
+        if dropped {
+            self.buffer.pop_front();
+        }
+
+        //the strongest knee in the window, windows too small to search have none
+        let knees = match self.params.smoothing {
+            SmoothingKind::Ema { alpha } => self.push_ema(point, alpha),
+            SmoothingKind::Gaussian if keeps_rows(&self.params) => self.push_gaussian(dropped),
+            _ => kneedle_ranked(self.buffer.make_contiguous(), &self.params),
+        };
+        self.knee = knees
+            .ok()
+            .and_then(|knees| knees.first().map(|knee| self.buffer[knee.index]));
+    }
+
+    //moves the average along to point and searches the smoothed window without smoothing it again
+    fn push_ema(&mut self, point: Point, alpha: f64) -> Result<Vec<KneeResult>, KneedleError> {
+        let y = match self.smoothed.back() {
+            Some(&[_, average]) if alpha > 0.0 && alpha < 1.0 => ema_step(average, point[1], alpha),
+            _ => point[1],
+        };
+        self.smoothed.push_back([point[0], y]);
+        if self.smoothed.len() > self.capacity {
+            self.smoothed.pop_front();
+        }
+
+        self.search_smoothed(self.params.monotonic_tolerance)
+    }
+
+    //smooths again the rows within the window of the new point, and of the dropped one when
+    //dropped, the other rows have the same neighbours as before the push. the search then checks
+    //the buffered points as the full search would before it searches the smoothed ones
+    fn push_gaussian(&mut self, dropped: bool) -> Result<Vec<KneeResult>, KneedleError> {
+        self.smoothed.push_back([0.0; 2]);
+        if dropped {
+            self.smoothed.pop_front();
+        }
+
+        let data = self.buffer.make_contiguous();
+        if data.is_empty() {
+            return Err(KneedleError::EmptyData);
+        }
+        let smoothed = self.smoothed.make_contiguous();
+        let w = self.weights.len() - 1;
+        let front = if dropped { 0..w.min(data.len()) } else { 0..0 };
+        let back = data.len().saturating_sub(w + 1)..data.len();
+        for i in front.chain(back) {
+            smooth_row(
+                data,
+                &self.weights,
+                None,
+                self.params.boundary,
+                i,
+                &mut smoothed[i],
+            );
+            if self.params.smooth_y_only {
+                smoothed[i][0] = data[i][0];
+            }
+        }
+
+        if let Some(row) = first_duplicate_x(data) {
+            return Err(KneedleError::DuplicateX { row });
+        }
+        if let Some(tolerance) = self.params.monotonic_tolerance {
+            if let Some(index) = first_non_monotonic(data, tolerance) {
+                return Err(KneedleError::NonMonotonic { index });
+            }
+        }
+        self.search_smoothed(None)
+    }
+
+    //the knees of the smoothed window, searched without smoothing it again and checked for
+    //monotonic y-values with monotonic_tolerance
+    fn search_smoothed(
+        &mut self,
+        monotonic_tolerance: Option<f64>,
+    ) -> Result<Vec<KneeResult>, KneedleError> {
+        let params = self
+            .params
+            .clone()
+            .smoothing(SmoothingKind::Gaussian)
+            .smoothing_window(0)
+            .monotonic_tolerance(monotonic_tolerance);
+        kneedle_ranked(self.smoothed.make_contiguous(), &params)
+    }
+
+    /// The most prominent knee among the buffered points, if there is one.
+    pub fn current_knee(&self) -> Option<Point> {
+        self.knee
+    }
+
+    /// Number of points currently buffered.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+/// Knee detection as an iterator adaptor, `points.knees(&params)`.
+pub trait KneeIteratorExt: Iterator<Item = Point> + Sized {
+    /// The knees of the points of this iterator, searched with `params`.
+    ///
+    /// The points are buffered and searched as a whole when the first knee is asked for, so the
+    /// adaptor is lazy but not incremental: no knee comes out before the last point has gone in.
+    /// The knees come out as `Ok`, or a single `Err` when the points cannot be searched, so
+    /// collecting into a `Result<Vec<_>, _>` gives the same as `kneedle_with`.
+    ///
+    /// ```
+    /// use kneedle::{KneeIteratorExt, KneedleParams};
+    ///
+    /// let curve = [0.0, 60.0, 80.0, 85.0, 90.0, 95.0, 96.0, 97.0, 98.0, 99.0];
+    /// let knees: Result<Vec<_>, _> = curve
+    ///     .iter()
+    ///     .enumerate()
+    ///     .map(|(x, &y)| [x as f64, y])
+    ///     .knees(&KneedleParams::new().smoothing_window(1))
+    ///     .collect();
+    /// assert_eq!(Ok(vec![[2.0, 80.0]]), knees);
+    /// ```
+    fn knees(self, params: &KneedleParams) -> Knees<Self> {
+        Knees {
+            state: KneesState::Pending(self, params.clone()),
+        }
+    }
+}
+
+impl<I: Iterator<Item = Point>> KneeIteratorExt for I {}
+
+/// The iterator returned by `KneeIteratorExt::knees`.
+#[derive(Clone, Debug)]
+pub struct Knees<I> {
+    state: KneesState<I>,
+}
+
+#[derive(Clone, Debug)]
+enum KneesState<I> {
+    Pending(I, KneedleParams),
+    Found(vec::IntoIter<Point>),
+    Failed(KneedleError),
+    Done,
+}
+
+impl<I: Iterator<Item = Point>> Iterator for Knees<I> {
+    type Item = Result<Point, KneedleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match core::mem::replace(&mut self.state, KneesState::Done) {
+                KneesState::Pending(points, params) => {
+                    let points: Vec<Point> = points.collect();
+                    self.state = match kneedle_with(&points, &params) {
+                        Ok(knees) => KneesState::Found(knees.into_iter()),
+                        Err(error) => KneesState::Failed(error),
+                    };
+                }
+                KneesState::Found(mut knees) => {
+                    let knee = knees.next()?;
+                    self.state = KneesState::Found(knees);
+                    return Some(Ok(knee));
+                }
+                KneesState::Failed(error) => return Some(Err(error)),
+                KneesState::Done => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_stream() {
+        let params = KneedleParams::new().smoothing_window(1);
+        let mut streaming = StreamingKneedle::new(10, params);
+        assert_eq!(None, streaming.current_knee());
+
+        let curve = [0.0, 60.0, 80.0, 85.0, 90.0, 95.0, 96.0, 97.0, 98.0, 99.0];
+        for (x, y) in curve.iter().enumerate() {
+            streaming.push([x as f64, *y]);
+        }
+        assert_eq!(10, streaming.len());
+        assert_eq!(Some([2.0, 80.0]), streaming.current_knee());
+
+        //shift the same shape along, the oldest points fall out of the window
+        for (x, y) in curve.iter().enumerate() {
+            streaming.push([(x + 10) as f64, 100.0 + *y]);
+        }
+        assert_eq!(10, streaming.len());
+        assert_eq!(Some([12.0, 180.0]), streaming.current_knee());
+    }
+
+    #[test]
+    fn gaussian_smoothed_incrementally() {
+        //a staircase of bends with a ripple on it, searched by window positions of 30 points
+        let signal: Vec<Point> = (0..120)
+            .map(|i| {
+                let t = i as f64;
+                let step = (i / 25) as f64 + 1.0 - (-((i % 25) as f64) / 3.0).exp();
+                [t, step + 0.02 * (1.7 * t).sin()]
+            })
+            .collect();
+
+        for params in [
+            KneedleParams::new().smoothing_window(0),
+            KneedleParams::new().smoothing_window(3),
+            KneedleParams::new()
+                .smoothing_window(3)
+                .boundary(crate::Boundary::Reflect),
+            KneedleParams::new().smoothing_window(2).smooth_y_only(true),
+            KneedleParams::new().smoothing_window(12),
+        ] {
+            let mut streaming = StreamingKneedle::new(30, params.clone());
+            let mut found = 0;
+            for (i, &point) in signal.iter().enumerate() {
+                streaming.push(point);
+                let window = &signal[(i + 1).saturating_sub(30)..=i];
+                let knee = crate::single_knee(window, &params).ok().flatten();
+                assert_eq!(knee, streaming.current_knee(), "{params:?} at {i}");
+                found += usize::from(knee.is_some());
+            }
+            assert!(found > 0);
+        }
+    }
+
+    #[test]
+    fn ema_lags_behind_gaussian() {
+        //flat, then a step up that levels off exponentially
+        let signal: Vec<Point> = (0..40)
+            .map(|i| {
+                let t = i as f64;
+                let y = if i < 20 {
+                    0.0
+                } else {
+                    1.0 - (-(t - 20.0) / 3.0).exp()
+                };
+                [t, y]
+            })
+            .collect();
+
+        //the point at which a knee is first reported, and the knee
+        let first_knee = |params: KneedleParams| {
+            let mut streaming = StreamingKneedle::new(30, params);
+            signal.iter().find_map(|&point| {
+                streaming.push(point);
+                streaming.current_knee().map(|knee| (point[0], knee[0]))
+            })
+        };
+
+        let gaussian = first_knee(KneedleParams::new().smoothing_window(2));
+        let ema = first_knee(KneedleParams::new().smoothing(SmoothingKind::Ema { alpha: 0.5 }));
+        assert_eq!(Some((31.0, 27.0)), gaussian);
+        //one point later, and one point further along the curve
+        assert_eq!(Some((32.0, 28.0)), ema);
+    }
+
+    #[test]
+    fn knees_adaptor() {
+        let curve = [
+            7305.0, 6979.0, 6666.6, 6463.2, 6326.5, 6048.8, 6032.8, 5762.0, 5742.8, 5398.2, 5256.8,
+            5227.0, 5001.7, 4942.0, 4854.2, 4734.6, 4558.7, 4491.1, 4411.6, 4333.0, 4234.6,
+        ];
+        let params = KneedleParams::new()
+            .smoothing_window(1)
+            .curve(crate::Curve::ConvexDecreasing);
+
+        let batch: Vec<Point> = curve
+            .iter()
+            .enumerate()
+            .map(|(x, &y)| [x as f64, y])
+            .collect();
+        let knees: Result<Vec<_>, _> = (0..curve.len())
+            .map(|x| [x as f64, curve[x]])
+            .knees(&params)
+            .collect();
+        assert!(knees.as_ref().is_ok_and(|knees| !knees.is_empty()));
+        assert_eq!(kneedle_with(&batch, &params), knees);
+
+        let mut too_short = [[0.0, 1.0], [1.0, 2.0]].into_iter().knees(&params);
+        assert_eq!(Some(Err(KneedleError::TooFewPoints(2))), too_short.next());
+        assert_eq!(None, too_short.next());
+    }
+}
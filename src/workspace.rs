@@ -0,0 +1,452 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::error::{KneedleError, Stage};
+use crate::float::Float;
+use crate::maths::{check_rows, fill_kernel_weights, smooth_rows};
+use crate::params::{KneedleParams, Normalization, SmoothingKind};
+use crate::{
+    clean_rows, select_knees, separate_knees, smooth, threshold_candidates, threshold_knees,
+    threshold_step, to_f64_points, validate, Knee, KneedleTrace, Point,
+};
+
+//finds the candidate indices of a difference curve in place of find_candidate_indices
+pub(crate) type CandidateDetector<'a> = dyn FnMut(&[Point]) -> Vec<usize> + 'a;
+
+/// Buffers for running the detection over many curves without allocating for each one.
+///
+/// `kneedle_with` allocates its intermediate curves afresh on every call. A workspace keeps them
+/// between calls and only grows them, so once it has seen input of a given size, searching input
+/// of that size again allocates nothing. That holds for Gaussian smoothing of ascending data that
+/// needs no clean-up; the other smoothers, reordering, skipped rows, merged duplicates, a log
+/// scale and a `min_separation` between several knees still allocate as `kneedle_with` does.
+///
+/// ```
+/// use kneedle::{KneedleParams, KneedleWorkspace};
+///
+/// let params = KneedleParams::new().smoothing_window(1);
+/// let mut workspace = KneedleWorkspace::new();
+/// for scale in [1.0, 2.0, 3.0] {
+///     let data: Vec<[f64; 2]> = [0.0, 0.55, 0.75, 0.825, 0.875, 0.9, 0.925, 0.95, 1.0]
+///         .iter()
+///         .enumerate()
+///         .map(|(i, &y)| [i as f64, scale * y])
+///         .collect();
+///     assert_eq!(&[[2.0, scale * 0.75]], workspace.kneedle(&data, &params).unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct KneedleWorkspace<T> {
+    weights: Vec<T>,
+    point_weights: Vec<T>,
+    flipped: Vec<[T; 2]>,
+    curve: Vec<[T; 2]>,
+    candidates: Vec<usize>,
+    pub(crate) knees: Vec<Knee<T>>,
+    points: Vec<[T; 2]>,
+    //filled with the stages of the next search when set, for kneedle_trace
+    pub(crate) trace: Option<KneedleTrace>,
+}
+
+impl<T: Float> Default for KneedleWorkspace<T> {
+    fn default() -> Self {
+        KneedleWorkspace {
+            weights: Vec::new(),
+            point_weights: Vec::new(),
+            flipped: Vec::new(),
+            curve: Vec::new(),
+            candidates: Vec::new(),
+            knees: Vec::new(),
+            points: Vec::new(),
+            trace: None,
+        }
+    }
+}
+
+impl<T: Float> KneedleWorkspace<T> {
+    /// An empty workspace, its buffers are allocated by the first search.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same as `kneedle_with`, with the knee points borrowed from the workspace until the next
+    /// search.
+    pub fn kneedle<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        params: &KneedleParams,
+    ) -> Result<&[[T; 2]], KneedleError> {
+        self.find_knees(data, None, None, params)?;
+
+        self.points.clear();
+        self.points.extend(self.knees.iter().map(|knee| {
+            let row = data[knee.index].as_ref();
+            [row[0], row[1]]
+        }));
+        Ok(&self.points)
+    }
+
+    //the knees of data with the detection of params, left in self.knees. point_weights, checked
+    //against data by the caller, scale the Gaussian smoothing, and detector replaces the search
+    //for candidates
+    pub(crate) fn find_knees<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        point_weights: Option<&[f64]>,
+        detector: Option<&mut CandidateDetector>,
+        params: &KneedleParams,
+    ) -> Result<(), KneedleError> {
+        validate(data)?;
+
+        let rows = clean_rows(data, params)?;
+
+        //the weights follow the rows into the order they are searched in
+        self.point_weights.clear();
+        if let Some(point_weights) = point_weights {
+            let weight = |i: usize| T::from_f64(point_weights[i]);
+            match &rows {
+                Some(rows) => self
+                    .point_weights
+                    .extend(rows.origin.iter().map(|&i| weight(i))),
+                None => self.point_weights.extend((0..data.len()).map(weight)),
+            }
+        }
+
+        match rows {
+            Some(rows) => {
+                //map the indices in the cleaned up copy back onto the rows they came from
+                self.find_oriented_knees(&rows.points, detector, params)?;
+                for knee in self.knees.iter_mut() {
+                    knee.index = rows.origin[knee.index];
+                }
+            }
+            None => self.find_oriented_knees(data, detector, params)?,
+        }
+
+        let knees = separate_knees(data, mem::take(&mut self.knees), params.min_separation);
+        self.knees = select_knees(data, knees, params.selection);
+        Ok(())
+    }
+
+    pub(crate) fn find_oriented_knees<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        detector: Option<&mut CandidateDetector>,
+        params: &KneedleParams,
+    ) -> Result<(), KneedleError> {
+        if !params.curve.is_decreasing() {
+            return self.detect(data, detector, params);
+        }
+
+        //decreasing curves are searched on their mirror image, so map the indices back
+        let mut flipped = mem::take(&mut self.flipped);
+        let mut xmax = T::MIN;
+        for row in data {
+            xmax = xmax.max(row.as_ref()[0]);
+        }
+        flipped.clear();
+        flipped.extend(data.iter().rev().map(|row| {
+            let row = row.as_ref();
+            [xmax - row[0], row[1]]
+        }));
+
+        self.point_weights.reverse();
+        let detected = self.detect(&flipped, detector, params);
+        self.point_weights.reverse();
+        self.flipped = flipped;
+        detected?;
+
+        for knee in self.knees.iter_mut() {
+            knee.index = data.len() - 1 - knee.index;
+        }
+        self.knees.reverse();
+        Ok(())
+    }
+
+    fn detect<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        detector: Option<&mut CandidateDetector>,
+        params: &KneedleParams,
+    ) -> Result<(), KneedleError> {
+        let find_elbow = params.curve.is_convex();
+
+        //do steps 1,2,3 of the paper in the prepare method
+        self.prepare(data, params)?;
+
+        //go through each candidate index, i, and see if the indices after i are satisfy the threshold requirement
+        //(this is step 5 in the paper)
+        let step = threshold_step(&self.curve, params);
+
+        match detector {
+            Some(detector) => {
+                let curve: Vec<Point> = self
+                    .curve
+                    .iter()
+                    .map(|row| [row[0].to_f64(), row[1].to_f64()])
+                    .collect();
+                self.candidates = detector(&curve);
+                //the threshold is walked in order, each index once
+                self.candidates.retain(|&i| i < curve.len());
+                self.candidates.sort_unstable();
+                self.candidates.dedup();
+                threshold_candidates(
+                    &self.curve,
+                    step,
+                    find_elbow,
+                    &self.candidates,
+                    &mut self.knees,
+                );
+            }
+            None => threshold_knees(
+                &self.curve,
+                step,
+                find_elbow,
+                params.include_endpoints,
+                T::from_f64(params.extremum_tolerance),
+                &mut self.candidates,
+                &mut self.knees,
+            ),
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.candidates.clone_from(&self.candidates);
+            trace.step = step.to_f64();
+        }
+        Ok(())
+    }
+
+    //steps 1 to 3 of the paper, the difference curve is left in self.curve
+    pub(crate) fn prepare<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        params: &KneedleParams,
+    ) -> Result<&[[T; 2]], KneedleError> {
+        self.normalize(data, params, true)
+    }
+
+    //steps 1 and 2 of the paper, the normalized curve is left in self.curve
+    pub(crate) fn smooth_and_normalize<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        params: &KneedleParams,
+    ) -> Result<&[[T; 2]], KneedleError> {
+        self.normalize(data, params, false)
+    }
+
+    //steps 1 and 2, and step 3 too when difference is set
+    fn normalize<I: AsRef<[T]>>(
+        &mut self,
+        data: &[I],
+        params: &KneedleParams,
+        difference: bool,
+    ) -> Result<&[[T; 2]], KneedleError> {
+        //smooth the data to make local minimum/maximum easier to find (this is Step 1 in the paper)
+        self.curve.clear();
+        match params.smoothing {
+            SmoothingKind::Gaussian => {
+                check_rows(data).map_err(|error| error.in_stage(Stage::Smoothing))?;
+                fill_kernel_weights(
+                    params.smoothing_window,
+                    params.gaussian_bandwidth,
+                    &mut self.weights,
+                );
+                let point_weights =
+                    (!self.point_weights.is_empty()).then_some(&self.point_weights[..]);
+                self.curve.resize(data.len(), [T::ZERO; 2]);
+                smooth_rows(
+                    data,
+                    &self.weights,
+                    point_weights,
+                    params.boundary,
+                    &mut self.curve,
+                );
+                if params.smooth_y_only {
+                    for (smoothed, row) in self.curve.iter_mut().zip(data) {
+                        smoothed[0] = row.as_ref()[0];
+                    }
+                }
+            }
+            _ => {
+                let smoothed =
+                    smooth(data, params).map_err(|error| error.in_stage(Stage::Smoothing))?;
+                self.curve
+                    .extend(smoothed.iter().map(|row| [row[0], row[1]]));
+            }
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.raw = to_f64_points(data);
+            trace.smoothed = to_f64_points(&self.curve);
+            trace.normalized.clear();
+        }
+
+        //normalize the data (step 2 of paper), as minmax_normalize or zscore_normalize do, and
+        //subtract normalised x from normalised y (step 3) in the same pass over the curve when
+        //the difference is wanted. the scale of both dimensions is found first, checking for
+        //values that cannot be scaled
+        if self.curve.is_empty() {
+            return Err(KneedleError::EmptyData.in_stage(Stage::Normalization));
+        }
+        let n = T::from_f64(self.curve.len() as f64);
+        let (mut low, mut high) = ([T::MAX; 2], [T::MIN; 2]);
+        let mut sum = [T::ZERO; 2];
+        for (i, row) in self.curve.iter().enumerate() {
+            if let Some(col) = row.iter().position(|value| !value.is_finite()) {
+                return Err(KneedleError::NonFinite { row: i, col }.in_stage(Stage::Normalization));
+            }
+            for d in 0..2 {
+                low[d] = low[d].min(row[d]);
+                high[d] = high[d].max(row[d]);
+                sum[d] += row[d];
+            }
+        }
+
+        let (offset, scale) = match params.normalization {
+            Normalization::MinMax => (low, [high[0] - low[0], high[1] - low[1]]),
+            Normalization::ZScore => {
+                let mean = [sum[0] / n, sum[1] / n];
+                let mut variance = [T::ZERO; 2];
+                for row in &self.curve {
+                    for d in 0..2 {
+                        let deviation = row[d] - mean[d];
+                        variance[d] += deviation * deviation;
+                    }
+                }
+                (mean, [(variance[0] / n).sqrt(), (variance[1] / n).sqrt()])
+            }
+        };
+        let normalize = |value: T, d: usize| {
+            if scale[d] == T::ZERO {
+                T::ZERO
+            } else {
+                (value - offset[d]) / scale[d]
+            }
+        };
+
+        for row in self.curve.iter_mut() {
+            let x = normalize(row[0], 0);
+            let y = normalize(row[1], 1);
+            *row = [x, if difference { y - x } else { y }];
+            if let Some(trace) = &mut self.trace {
+                trace.normalized.push([x.to_f64(), y.to_f64()]);
+            }
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.difference = to_f64_points(&self.curve);
+        }
+
+        Ok(&self.curve)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maths::{
+        gaussian_smooth2d, gaussian_smooth2d_with_bandwidth, gaussian_smooth2d_with_boundary,
+        minmax_normalize, zscore_normalize,
+    };
+    use crate::params::Boundary;
+    use crate::{kneedle_with, Curve};
+
+    fn curve(n: usize, shift: f64) -> Vec<Point> {
+        (1..=n)
+            .map(|i| [i as f64, shift - 1.0 / i as f64])
+            .collect()
+    }
+
+    #[test]
+    fn matches_kneedle_with() {
+        let mut workspace = KneedleWorkspace::new();
+        let decreasing: Vec<Point> = curve(20, 0.0).iter().map(|&[x, y]| [x, -y]).collect();
+        let cases = [
+            (curve(20, 0.0), KneedleParams::new()),
+            (curve(20, 0.0), KneedleParams::new().smooth_y_only(true)),
+            (
+                decreasing,
+                KneedleParams::new().curve(Curve::ConvexDecreasing),
+            ),
+            (
+                curve(20, 0.0),
+                KneedleParams::new().smoothing(SmoothingKind::Median { window: 3 }),
+            ),
+        ];
+        for (data, params) in &cases {
+            assert_eq!(
+                kneedle_with(data, params).unwrap(),
+                workspace.kneedle(data, params).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn prepare_matches_the_stages() {
+        let test_data = crate::tests::bumpy_data();
+
+        for (normalization, normalize) in [
+            (
+                Normalization::MinMax,
+                minmax_normalize as fn(Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, KneedleError>,
+            ),
+            (Normalization::ZScore, zscore_normalize),
+        ] {
+            let params = KneedleParams::new().normalization(normalization);
+            let staged: Vec<Point> = normalize(gaussian_smooth2d(&test_data, 3).unwrap())
+                .unwrap()
+                .iter()
+                .map(|row| [row[0], row[1] - row[0]])
+                .collect();
+
+            let mut workspace = KneedleWorkspace::new();
+            assert_eq!(&staged[..], workspace.prepare(&test_data, &params).unwrap());
+        }
+
+        for (params, smoothed) in [
+            (
+                KneedleParams::new().gaussian_bandwidth(Some(1.5)),
+                gaussian_smooth2d_with_bandwidth(&test_data, 3, 1.5).unwrap(),
+            ),
+            (
+                KneedleParams::new().boundary(Boundary::Reflect),
+                gaussian_smooth2d_with_boundary(&test_data, 3, Boundary::Reflect).unwrap(),
+            ),
+        ] {
+            let staged: Vec<Point> = minmax_normalize(smoothed)
+                .unwrap()
+                .iter()
+                .map(|row| [row[0], row[1] - row[0]])
+                .collect();
+            let mut workspace = KneedleWorkspace::new();
+            assert_eq!(&staged[..], workspace.prepare(&test_data, &params).unwrap());
+        }
+    }
+
+    #[test]
+    fn errors_name_their_stage() {
+        //ragged rows are rejected up front, before any stage runs, so they carry the row that
+        //broke rather than a stage
+        let ragged = vec![vec![0.0, 0.0], vec![1.0, 0.5], vec![2.0], vec![3.0, 0.9]];
+        assert_eq!(
+            Err(KneedleError::RaggedRows {
+                row: 2,
+                expected: 2,
+                got: 1
+            }),
+            kneedle_with(&ragged, &KneedleParams::new())
+        );
+
+        //a failing smoother is named
+        let params = KneedleParams::new().smoothing(SmoothingKind::Median { window: 4 });
+        let test_data = curve(10, 0.0);
+        let error = kneedle_with(&test_data, &params).unwrap_err();
+        assert_eq!(
+            KneedleError::EvenWindow(4).in_stage(Stage::Smoothing),
+            error
+        );
+        assert_eq!(
+            "smoothing failed: smoothing window must be odd, got 4",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//the counting allocator replaces the global allocator of this test binary only, so it is kept
+//apart from the unit tests
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use kneedle::{KneedleParams, KneedleWorkspace, Point};
+
+//counts the allocations of the current thread, so tests running alongside do not interfere
+struct CountingAllocator;
+
+std::thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+fn curve(n: usize, shift: f64) -> Vec<Point> {
+    (1..=n)
+        .map(|i| [i as f64, shift - 1.0 / i as f64])
+        .collect()
+}
+
+#[test]
+fn no_allocations_after_the_first_call() {
+    let params = KneedleParams::new();
+    let mut workspace = KneedleWorkspace::new();
+    let curves: Vec<Vec<Point>> = (0..4).map(|i| curve(200, i as f64)).collect();
+
+    let before = allocations();
+    assert_eq!(1, workspace.kneedle(&curves[0], &params).unwrap().len());
+    assert!(allocations() > before);
+
+    for data in &curves[1..] {
+        let before = allocations();
+        let knees = workspace.kneedle(data, &params).unwrap().len();
+        assert_eq!(0, allocations() - before);
+        assert_eq!(1, knees);
+    }
+}